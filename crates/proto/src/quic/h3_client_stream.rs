@@ -0,0 +1,326 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use alloc::{boxed::Box, sync::Arc};
+use core::{
+    fmt::{self, Display},
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::{future::poll_fn, net::SocketAddr};
+
+use bytes::{Buf, Bytes};
+use data_encoding::BASE64URL_NOPAD;
+use futures_util::{
+    future::{BoxFuture, FutureExt},
+    stream::Stream,
+};
+use h3::client::SendRequest;
+use h3_quinn::{Connection as H3QuinnConnection, OpenStreams};
+use http::{Method, Request, header::CONTENT_TYPE};
+use quinn::{Endpoint, TransportConfig};
+use tracing::debug;
+
+use crate::{
+    error::{ProtoError, ProtoErrorKind},
+    rustls::client_config,
+    udp::UdpSocket,
+    xfer::{DnsRequest, DnsRequestSender, DnsResponse, DnsResponseStream},
+};
+
+use super::{quic_client_stream::connect_quic, quic_config};
+
+/// ```text
+/// RFC 9114    HTTP/3
+///
+/// DoH3 connections are established by selecting the Application-Layer Protocol Negotiation
+/// (ALPN) token "h3" in the crypto handshake, same as any other HTTP/3 endpoint.
+/// ```
+pub(crate) const H3_ALPN: &[u8] = b"h3";
+
+/// The path DoH servers expect queries to be sent to (by POST or GET), see
+/// [RFC 8484, section 4.1](https://www.rfc-editor.org/rfc/rfc8484#section-4.1).
+const DEFAULT_DNS_QUERY_PATH: &str = "/dns-query";
+
+/// The content-type DoH requires for a DNS wireformat message body, see
+/// [RFC 8484, section 6](https://www.rfc-editor.org/rfc/rfc8484#section-6).
+const DNS_MESSAGE_CONTENT_TYPE: &str = "application/dns-message";
+
+/// A DNS client connection for DNS-over-HTTP/3 (DoH3), layered over the same QUIC connection
+/// machinery used for DoQ, but selecting ALPN `h3` and framing each query as an HTTP/3 request
+/// instead of a raw length-prefixed DoQ stream.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Clone)]
+pub struct H3ClientStream {
+    server_name: Arc<str>,
+    name_server: SocketAddr,
+    path: Arc<str>,
+    use_get: bool,
+    send_request: SendRequest<OpenStreams, Bytes>,
+    is_shutdown: bool,
+}
+
+impl Display for H3ClientStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(formatter, "H3({},{})", self.name_server, self.server_name)
+    }
+}
+
+impl H3ClientStream {
+    /// Builder for H3ClientStream
+    pub fn builder() -> H3ClientStreamBuilder {
+        H3ClientStreamBuilder::default()
+    }
+
+    async fn inner_send(
+        mut send_request: SendRequest<OpenStreams, Bytes>,
+        server_name: Arc<str>,
+        path: Arc<str>,
+        use_get: bool,
+        message: DnsRequest,
+    ) -> Result<DnsResponse, ProtoError> {
+        let bytes = message.into_parts().0.to_vec()?;
+
+        // RFC 8484 section 4.1 allows either a POST with the message as the body, or a GET with
+        // the message base64url-encoded in the `dns` query parameter; POST is the default, since
+        // it matches the other DNS transports in this crate that send the raw wireformat message
+        // as-is, but some DoH3-only deployments require GET for cache-friendliness.
+        let (request, body) = if use_get {
+            let encoded = BASE64URL_NOPAD.encode(&bytes);
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(format!("{path}?dns={encoded}"))
+                .header(http::header::HOST, server_name.as_ref())
+                .body(())
+                .map_err(|e| ProtoError::from(ProtoErrorKind::Msg(format!("invalid DoH3 request: {e}"))))?;
+            (request, None)
+        } else {
+            let request = Request::post(path.as_ref())
+                .header(http::header::HOST, server_name.as_ref())
+                .header(CONTENT_TYPE, DNS_MESSAGE_CONTENT_TYPE)
+                .body(())
+                .map_err(|e| ProtoError::from(ProtoErrorKind::Msg(format!("invalid DoH3 request: {e}"))))?;
+            (request, Some(bytes))
+        };
+
+        let mut stream = send_request
+            .send_request(request)
+            .await
+            .map_err(|e| ProtoError::from(ProtoErrorKind::Msg(format!("DoH3 request failed: {e}"))))?;
+
+        if let Some(bytes) = body {
+            stream
+                .send_data(Bytes::from(bytes))
+                .await
+                .map_err(|e| ProtoError::from(ProtoErrorKind::Msg(format!("DoH3 send failed: {e}"))))?;
+        }
+        stream
+            .finish()
+            .await
+            .map_err(|e| ProtoError::from(ProtoErrorKind::Msg(format!("DoH3 finish failed: {e}"))))?;
+
+        let response = stream
+            .recv_response()
+            .await
+            .map_err(|e| ProtoError::from(ProtoErrorKind::Msg(format!("DoH3 response failed: {e}"))))?;
+
+        if !response.status().is_success() {
+            return Err(ProtoErrorKind::Msg(format!(
+                "DoH3 server returned status {}",
+                response.status()
+            ))
+            .into());
+        }
+
+        let mut body = Vec::new();
+        while let Some(mut chunk) = stream
+            .recv_data()
+            .await
+            .map_err(|e| ProtoError::from(ProtoErrorKind::Msg(format!("DoH3 body read failed: {e}"))))?
+        {
+            body.extend_from_slice(chunk.copy_to_bytes(chunk.remaining()).as_ref());
+        }
+
+        DnsResponse::from_buffer(body)
+    }
+}
+
+impl DnsRequestSender for H3ClientStream {
+    /// Opens a new HTTP/3 request stream per query and decodes the response body as a DNS message,
+    /// per [RFC 9114](https://www.rfc-editor.org/rfc/rfc9114) carrying
+    /// [RFC 8484](https://www.rfc-editor.org/rfc/rfc8484) `application/dns-message` payloads.
+    fn send_message(&mut self, request: DnsRequest) -> DnsResponseStream {
+        if self.is_shutdown {
+            panic!("can not send messages after stream is shutdown")
+        }
+
+        Box::pin(Self::inner_send(
+            self.send_request.clone(),
+            self.server_name.clone(),
+            self.path.clone(),
+            self.use_get,
+            request,
+        ))
+        .into()
+    }
+
+    fn shutdown(&mut self) {
+        self.is_shutdown = true;
+    }
+
+    fn is_shutdown(&self) -> bool {
+        self.is_shutdown
+    }
+}
+
+impl Stream for H3ClientStream {
+    type Item = Result<(), ProtoError>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.is_shutdown {
+            Poll::Ready(None)
+        } else {
+            Poll::Ready(Some(Ok(())))
+        }
+    }
+}
+
+/// A QUIC connection builder for DNS-over-HTTP/3
+#[derive(Clone)]
+pub struct H3ClientStreamBuilder {
+    crypto_config: Option<rustls::ClientConfig>,
+    transport_config: Arc<TransportConfig>,
+    bind_addr: Option<SocketAddr>,
+    path: Arc<str>,
+    use_get: bool,
+}
+
+impl H3ClientStreamBuilder {
+    /// Constructs a new H3ClientStreamBuilder with the associated ClientConfig
+    pub fn crypto_config(mut self, crypto_config: rustls::ClientConfig) -> Self {
+        self.crypto_config = Some(crypto_config);
+        self
+    }
+
+    /// Sets the address to connect from.
+    pub fn bind_addr(mut self, bind_addr: SocketAddr) -> Self {
+        self.bind_addr = Some(bind_addr);
+        self
+    }
+
+    /// Sets the HTTP path queries are sent to (as a POST, or a GET with [`Self::use_get`]),
+    /// overriding the default of `/dns-query`
+    /// (see [RFC 8484, section 4.1](https://www.rfc-editor.org/rfc/rfc8484#section-4.1)).
+    pub fn path(mut self, path: impl Into<Arc<str>>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Sends queries as an HTTP GET with the message base64url-encoded in the `dns` query
+    /// parameter instead of the default POST with the raw wireformat message as the body (see
+    /// [RFC 8484, section 4.1](https://www.rfc-editor.org/rfc/rfc8484#section-4.1)). GET responses
+    /// are cacheable by intermediate HTTP caches, at the cost of URL-length limits on large queries.
+    pub fn use_get(mut self) -> Self {
+        self.use_get = true;
+        self
+    }
+
+    /// Creates a new H3ClientStream to the specified name_server
+    ///
+    /// # Arguments
+    ///
+    /// * `name_server` - IP and Port for the remote DNS resolver
+    /// * `server_name` - The DNS name associated with a certificate
+    pub fn build(self, name_server: SocketAddr, server_name: Arc<str>) -> H3ClientConnect {
+        H3ClientConnect(Box::pin(self.connect(name_server, server_name)) as _)
+    }
+
+    async fn connect(
+        self,
+        name_server: SocketAddr,
+        server_name: Arc<str>,
+    ) -> Result<H3ClientStream, ProtoError> {
+        let connect = if let Some(bind_addr) = self.bind_addr {
+            <tokio::net::UdpSocket as UdpSocket>::connect_with_bind(name_server, bind_addr)
+        } else {
+            <tokio::net::UdpSocket as UdpSocket>::connect(name_server)
+        };
+
+        let socket = connect.await?;
+        let socket = socket.into_std()?;
+        let endpoint_config = quic_config::endpoint();
+        let endpoint = Endpoint::new(endpoint_config, None, socket, Arc::new(quinn::TokioRuntime))?;
+
+        let crypto_config = if let Some(crypto_config) = self.crypto_config {
+            crypto_config
+        } else {
+            client_config()?
+        };
+
+        // DoH3 has no notion of a replay-unsafe request the way DoQ's DNS UPDATE does (HTTP/3
+        // itself governs what's retriable), so the 0-RTT confirmation state `connect_quic` tracks
+        // isn't needed here.
+        let (quic_connection, _early_data) = connect_quic(
+            name_server,
+            server_name.clone(),
+            H3_ALPN,
+            crypto_config,
+            self.transport_config,
+            endpoint,
+        )
+        .await?;
+
+        let (mut h3_connection, send_request) = h3::client::new(H3QuinnConnection::new(quic_connection))
+            .await
+            .map_err(|e| ProtoError::from(ProtoErrorKind::Msg(format!("DoH3 handshake failed: {e}"))))?;
+
+        // The `Connection` half returned by `h3::client::new` must keep being polled for the
+        // `SendRequest` handle to make any progress; there's no long-lived executor handle
+        // threaded through this builder, so we spawn it onto whichever runtime is already driving
+        // everything else, the same way `QuicClientStream` leaves its connection driven by quinn.
+        tokio::spawn(async move {
+            if let Err(error) = poll_fn(|cx| h3_connection.poll_close(cx)).await {
+                debug!(%error, "DoH3 connection closed with error");
+            }
+        });
+
+        Ok(H3ClientStream {
+            server_name,
+            name_server,
+            path: self.path,
+            use_get: self.use_get,
+            send_request,
+            is_shutdown: false,
+        })
+    }
+}
+
+impl Default for H3ClientStreamBuilder {
+    fn default() -> Self {
+        let transport_config = quic_config::transport();
+
+        Self {
+            crypto_config: None,
+            transport_config: Arc::new(transport_config),
+            bind_addr: None,
+            path: Arc::from(DEFAULT_DNS_QUERY_PATH),
+            use_get: false,
+        }
+    }
+}
+
+/// A future that resolves to an H3ClientStream
+pub struct H3ClientConnect(BoxFuture<'static, Result<H3ClientStream, ProtoError>>);
+
+impl Future for H3ClientConnect {
+    type Output = Result<H3ClientStream, ProtoError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.0.poll_unpin(cx)
+    }
+}
@@ -0,0 +1,140 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use alloc::sync::Arc;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use quinn::{Endpoint, TransportConfig};
+
+use crate::error::ProtoError;
+
+use super::quic_client_stream::{DoqLimits, QuicClientConnection, connect_quic};
+use super::quic_stream::DOQ_ALPN;
+
+/// How long a cached connection may sit unused before [`QuicClientPool`] treats it as idle and
+/// reconnects instead of reusing it, see [`QuicClientPool::idle_timeout`].
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Identifies a cached connection: the address it was opened to plus the TLS server name used
+/// for its handshake, since the same address can be reached under different server names.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct ConnectionKey {
+    name_server: SocketAddr,
+    server_name: Arc<str>,
+}
+
+/// A cached connection plus when it was last handed out, so [`QuicClientPool`] can evict one that
+/// has gone idle even though it's still open.
+struct CachedConnection {
+    connection: QuicClientConnection,
+    last_used: Instant,
+}
+
+/// A shared QUIC `Endpoint` plus a keyed cache of live DoQ connections, so repeatedly resolving
+/// through the same upstream reuses one handshake and congestion-controlled connection instead
+/// of paying full connection setup, and a fresh UDP socket, per query.
+///
+/// RFC 9250 section 4.2.1 notes that DoQ isn't limited by the DNS Message-ID space the way
+/// TCP/UDP transports are, so a single cached connection can freely multiplex up to
+/// [`DoqLimits::max_concurrent_streams`] concurrent queries before it is closed for excessive load.
+#[derive(Clone)]
+pub struct QuicClientPool {
+    endpoint: Endpoint,
+    limits: DoqLimits,
+    idle_timeout: Duration,
+    connections: Arc<Mutex<HashMap<ConnectionKey, CachedConnection>>>,
+}
+
+impl QuicClientPool {
+    /// Creates a new pool that shares `endpoint` across every connection it caches, applying
+    /// `limits` (in-flight query cap and per-stream response timeout) to each one. Connections
+    /// idle for longer than [`DEFAULT_IDLE_TIMEOUT`] are reconnected rather than reused; see
+    /// [`Self::idle_timeout`] to change that.
+    pub fn new(endpoint: Endpoint, limits: DoqLimits) -> Self {
+        Self {
+            endpoint,
+            limits,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            connections: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Sets how long a cached connection may go unused before it's treated as idle and
+    /// reconnected on its next lookup instead of reused, superseding [`DEFAULT_IDLE_TIMEOUT`].
+    pub fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Returns a cached connection to `name_server`/`server_name` if one is still healthy and
+    /// hasn't gone idle, otherwise establishes a new one and caches it, evicting whatever was
+    /// cached for this key before.
+    pub(crate) async fn get_or_connect(
+        &self,
+        name_server: SocketAddr,
+        server_name: Arc<str>,
+        crypto_config: rustls::ClientConfig,
+        transport_config: Arc<TransportConfig>,
+    ) -> Result<QuicClientConnection, ProtoError> {
+        let key = ConnectionKey {
+            name_server,
+            server_name: server_name.clone(),
+        };
+
+        if let Some(connection) = self.usable_cached(&key) {
+            return Ok(connection);
+        }
+
+        let (connection, early_data) = connect_quic(
+            name_server,
+            server_name,
+            DOQ_ALPN,
+            crypto_config,
+            transport_config,
+            self.endpoint.clone(),
+        )
+        .await?;
+
+        let connection = QuicClientConnection::new(connection, self.limits, early_data);
+        self.connections.lock().expect("QuicClientPool connections lock poisoned").insert(
+            key,
+            CachedConnection {
+                connection: connection.clone(),
+                last_used: Instant::now(),
+            },
+        );
+        Ok(connection)
+    }
+
+    /// Looks up `key`, returning the cached connection (and bumping its last-used time) if it's
+    /// still healthy and hasn't sat idle past [`Self::idle_timeout`]. A closed, draining, or idle
+    /// connection is evicted instead of being returned, so the next call reconnects.
+    fn usable_cached(&self, key: &ConnectionKey) -> Option<QuicClientConnection> {
+        let mut connections = self
+            .connections
+            .lock()
+            .expect("QuicClientPool connections lock poisoned");
+
+        match connections.get_mut(key) {
+            Some(cached)
+                if cached.connection.is_healthy()
+                    && cached.last_used.elapsed() < self.idle_timeout =>
+            {
+                cached.last_used = Instant::now();
+                Some(cached.connection.clone())
+            }
+            Some(_) => {
+                connections.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+}
@@ -10,34 +10,299 @@ use core::{
     fmt::{self, Display},
     future::Future,
     pin::Pin,
+    sync::atomic::{AtomicU8, AtomicUsize, Ordering},
     task::{Context, Poll},
 };
-use std::{io, net::SocketAddr};
+use std::{io, net::SocketAddr, time::Duration};
 
 use futures_util::{
-    future::{BoxFuture, FutureExt},
-    stream::Stream,
+    future::{BoxFuture, FutureExt, TryFutureExt},
+    stream::{self, Stream, StreamExt},
 };
 use quinn::{
     ClientConfig, Connection, Endpoint, TransportConfig, VarInt, crypto::rustls::QuicClientConfig,
 };
-use tokio::time::timeout;
+use tokio::{
+    sync::{mpsc, watch},
+    time::timeout,
+};
 
 use crate::{
-    error::ProtoError,
+    error::{ProtoError, ProtoErrorKind},
     quic::quic_stream::{DoqErrorCode, QuicStream},
+    rr::{OpCode, RecordType},
     rustls::client_config,
     udp::UdpSocket,
     xfer::{CONNECT_TIMEOUT, DnsRequest, DnsRequestSender, DnsResponse, DnsResponseStream},
 };
 
-use super::{quic_config, quic_stream};
+use super::{quic_client_pool::QuicClientPool, quic_config, quic_stream};
+
+/// The default cap on the number of DoQ queries a single [`QuicClientStream`] will have open at
+/// once before it closes its connection with `DOQ_EXCESSIVE_LOAD`, see
+/// [`DoqLimits::max_concurrent_streams`].
+const DEFAULT_MAX_QUERIES: usize = 100;
+
+/// The default per-stream deadline for a response to arrive, see [`DoqLimits::response_timeout`].
+const DEFAULT_RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Limits [`QuicClientStreamBuilder`] enforces per connection, so a client monitors and bounds
+/// the "dangling" streams RFC 9250 section 4.2 warns about: streams where the expected response
+/// (or its STREAM FIN) never arrives within an implementation-defined timeout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DoqLimits {
+    /// The maximum number of DoQ queries a connection will have open at once; a query beyond it
+    /// fails and closes the whole connection with `DOQ_EXCESSIVE_LOAD`, failing the queries still
+    /// in flight on it as well. Defaults to [`DEFAULT_MAX_QUERIES`].
+    pub max_concurrent_streams: usize,
+    /// How long a stream will wait for its response (or STREAM FIN) before it's considered
+    /// dangling and fails with a timeout error. Defaults to [`DEFAULT_RESPONSE_TIMEOUT`].
+    pub response_timeout: Duration,
+}
+
+impl Default for DoqLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent_streams: DEFAULT_MAX_QUERIES,
+            response_timeout: DEFAULT_RESPONSE_TIMEOUT,
+        }
+    }
+}
+
+/// Pending: the connection used 0-RTT and its handshake hasn't been confirmed yet.
+const EARLY_DATA_PENDING: u8 = 0;
+/// The server accepted the 0-RTT session resumption, and the handshake has since been confirmed.
+const EARLY_DATA_ACCEPTED: u8 = 1;
+/// The server rejected the 0-RTT session resumption, and the handshake has since been confirmed
+/// with a full round trip.
+const EARLY_DATA_REJECTED: u8 = 2;
+
+/// Whether a connection used 0-RTT early data and, if so, how that attempt resolved. See
+/// [`QuicClientStream::early_data_status`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EarlyDataStatus {
+    /// This connection never attempted 0-RTT: either [`QuicClientStreamBuilder::early_data`] was
+    /// never called, or the server didn't offer session resumption to begin with.
+    NotOffered,
+    /// 0-RTT was attempted and the handshake hasn't been confirmed yet. Data sent in this window
+    /// is replayable (RFC 9001 section 8.1), so [`QuicClientStream`] only sends requests it
+    /// considers idempotent while this is the status; see
+    /// [`QuicClientStream::is_early_data_safe`].
+    Pending,
+    /// The server accepted the 0-RTT session resumption, and the handshake has since been
+    /// confirmed.
+    Accepted,
+    /// The server rejected the 0-RTT session resumption; the handshake has since been confirmed
+    /// with a full round trip, same as a connection that never attempted early data.
+    Rejected,
+}
+
+/// Tracks the replay-safety window of a connection that attempted 0-RTT: [`Self::pending`] starts
+/// out unconfirmed, and [`Self::wait_confirmed`] resolves once quinn reports how the server
+/// responded to the session resumption attempt. A connection that never attempted 0-RTT is always
+/// already confirmed.
+#[derive(Clone)]
+struct EarlyData(Option<Arc<EarlyDataState>>);
+
+struct EarlyDataState {
+    status: AtomicU8,
+    confirmed: watch::Receiver<bool>,
+}
+
+impl EarlyData {
+    /// A connection with no replay-safety window to wait out, either because it never attempted
+    /// 0-RTT or because quinn didn't accept the 0-RTT attempt, so it's already fully handshaked.
+    fn confirmed() -> Self {
+        Self(None)
+    }
+
+    /// A connection that took the 0-RTT path; `accepted` resolves once quinn knows whether the
+    /// server accepted the session resumption.
+    fn pending(accepted: quinn::ZeroRttAccepted) -> Self {
+        let (tx, rx) = watch::channel(false);
+        let state = Arc::new(EarlyDataState {
+            status: AtomicU8::new(EARLY_DATA_PENDING),
+            confirmed: rx,
+        });
+
+        let confirm_state = state.clone();
+        tokio::spawn(async move {
+            let accepted = accepted.await;
+            confirm_state.status.store(
+                if accepted {
+                    EARLY_DATA_ACCEPTED
+                } else {
+                    EARLY_DATA_REJECTED
+                },
+                Ordering::Release,
+            );
+            let _ = tx.send(true);
+        });
+
+        Self(Some(state))
+    }
+
+    /// Waits for the handshake to be confirmed, if it hasn't been already. A no-op for a
+    /// connection that never attempted 0-RTT.
+    async fn wait_confirmed(&self) {
+        let Some(state) = &self.0 else { return };
+        let mut confirmed = state.confirmed.clone();
+        if !*confirmed.borrow() {
+            let _ = confirmed.changed().await;
+        }
+    }
+
+    fn status(&self) -> EarlyDataStatus {
+        match &self.0 {
+            None => EarlyDataStatus::NotOffered,
+            Some(state) => match state.status.load(Ordering::Acquire) {
+                EARLY_DATA_ACCEPTED => EarlyDataStatus::Accepted,
+                EARLY_DATA_REJECTED => EarlyDataStatus::Rejected,
+                _ => EarlyDataStatus::Pending,
+            },
+        }
+    }
+}
+
+/// The state needed to actually drive queries on a connection: the underlying `quinn::Connection`
+/// (itself cheaply cloneable, RFC 9250 section 4.2 expects one stream per query on it), a count of
+/// queries currently in flight (so excessive load can be detected), and its 0-RTT status.
+/// [`QuicClientConnection::drive`] is the only thing that opens streams through this; every
+/// other caller hands its query to [`QuicClientConnection::dispatch`] instead.
+#[derive(Clone)]
+struct ConnectionHandle {
+    connection: Connection,
+    in_flight: Arc<AtomicUsize>,
+    limits: DoqLimits,
+    early_data: EarlyData,
+}
+
+impl ConnectionHandle {
+    /// Reserves a slot for a new in-flight query, or closes the connection with
+    /// `DOQ_EXCESSIVE_LOAD` if it's already at its limit. RFC 9250 section 4.3 defines
+    /// `DOQ_EXCESSIVE_LOAD` as being for "closing a connection due to excessive load" (not for
+    /// refusing a single stream), so hitting the limit actually tears the connection down and
+    /// tells the peer why, rather than merely failing the one query locally; callers pooling
+    /// connections (see [`super::quic_client_pool::QuicClientPool::usable_cached`]) already treat
+    /// a closed connection as unhealthy and will reconnect on their next lookup.
+    fn reserve(&self) -> Result<InFlightGuard, ProtoError> {
+        let mut current = self.in_flight.load(Ordering::Acquire);
+        loop {
+            if current >= self.limits.max_concurrent_streams {
+                self.connection
+                    .close(DoqErrorCode::ExcessiveLoad.into(), b"DOQ_EXCESSIVE_LOAD");
+                return Err(ProtoErrorKind::Msg(format!(
+                    "DOQ_EXCESSIVE_LOAD: {current} DoQ queries already in flight on this connection, at the limit of {}; closing connection",
+                    self.limits.max_concurrent_streams
+                ))
+                .into());
+            }
+
+            match self.in_flight.compare_exchange_weak(
+                current,
+                current + 1,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    return Ok(InFlightGuard {
+                        in_flight: self.in_flight.clone(),
+                    });
+                }
+                Err(observed) => current = observed,
+            }
+        }
+    }
+}
+
+/// A query handed from [`QuicClientStream::send_message`] (or
+/// [`QuicClientStream::send_message_with_early_data_status`]) to a connection's background
+/// dispatcher task (see [`QuicClientConnection::drive`]), plus where to deliver its response(s).
+///
+/// Modeled on the Android DnsResolver `Connection`/`Network`/`Dispatcher` split: the dispatcher
+/// task is the only thing that actually multiplexes `open_bi()` calls onto the shared
+/// `quinn::Connection`, so callers hand off a query over this channel instead of driving the
+/// stream themselves.
+struct DispatchRequest {
+    message: DnsRequest,
+    responses: mpsc::UnboundedSender<(Result<DnsResponse, ProtoError>, EarlyDataStatus)>,
+}
+
+/// Per-connection bookkeeping shared by every clone of a [`QuicClientStream`]: a channel to the
+/// background dispatcher task that actually drives queries on the connection (see [`Self::drive`]).
+#[derive(Clone)]
+pub(crate) struct QuicClientConnection {
+    handle: ConnectionHandle,
+    dispatch: mpsc::UnboundedSender<DispatchRequest>,
+}
+
+impl QuicClientConnection {
+    pub(crate) fn new(connection: Connection, limits: DoqLimits, early_data: EarlyData) -> Self {
+        let handle = ConnectionHandle {
+            connection,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            limits,
+            early_data,
+        };
+
+        let (dispatch, requests) = mpsc::unbounded_channel();
+        tokio::spawn(Self::drive(handle.clone(), requests));
+
+        Self { handle, dispatch }
+    }
+
+    /// The background task every clone of a connection shares: the single place that actually
+    /// issues streams on the underlying connection. Each request is driven on its own spawned
+    /// task so one slow (or, for AXFR/IXFR, multi-response) query can't hold up the rest; the
+    /// driver's own job is just routing incoming requests, the same concurrency callers
+    /// previously got from calling `open_bi()` directly themselves.
+    async fn drive(handle: ConnectionHandle, mut requests: mpsc::UnboundedReceiver<DispatchRequest>) {
+        while let Some(request) = requests.recv().await {
+            let handle = handle.clone();
+            tokio::spawn(async move {
+                if QuicClientStream::is_xfr(&request.message) {
+                    QuicClientStream::dispatch_xfr(handle, request).await;
+                } else {
+                    QuicClientStream::dispatch_single(handle, request).await;
+                }
+            });
+        }
+    }
+
+    /// Whether this connection is still usable, i.e. not closed or in the process of closing.
+    /// Used by [`super::quic_client_pool::QuicClientPool`] to decide whether a cached connection
+    /// can be reused or must be replaced.
+    pub(crate) fn is_healthy(&self) -> bool {
+        self.handle.connection.close_reason().is_none()
+    }
+
+    /// Whether this connection used 0-RTT, and if so, how that attempt resolved.
+    pub(crate) fn early_data_status(&self) -> EarlyDataStatus {
+        self.handle.early_data.status()
+    }
+
+    fn close(&self, code: DoqErrorCode, reason: &'static [u8]) {
+        self.handle.connection.close(code.into(), reason);
+    }
+}
+
+/// Releases the slot reserved by [`ConnectionHandle::reserve`] once a query completes or is
+/// cancelled.
+struct InFlightGuard {
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
 
 /// A DNS client connection for DNS-over-QUIC
 #[must_use = "futures do nothing unless polled"]
 #[derive(Clone)]
 pub struct QuicClientStream {
-    quic_connection: Connection,
+    quic_connection: QuicClientConnection,
     server_name: Arc<str>,
     name_server: SocketAddr,
     is_shutdown: bool,
@@ -55,15 +320,117 @@ impl QuicClientStream {
         QuicClientStreamBuilder::default()
     }
 
+    /// Hands `message` to `connection`'s background dispatcher task (see
+    /// [`QuicClientConnection::drive`]) and waits for its single response, rather than opening a
+    /// stream directly.
     async fn inner_send(
-        connection: Connection,
+        connection: QuicClientConnection,
+        message: DnsRequest,
+    ) -> (Result<DnsResponse, ProtoError>, EarlyDataStatus) {
+        let (responses, mut rx) = mpsc::unbounded_channel();
+        if connection
+            .dispatch
+            .send(DispatchRequest { message, responses })
+            .is_err()
+        {
+            // The dispatcher task only exits once every `QuicClientConnection` clone (and thus
+            // every `dispatch` sender) has been dropped, so a caller that can still reach this
+            // `connection` should never observe a closed channel; handled defensively rather than
+            // panicking.
+            return (
+                Err(ProtoErrorKind::Msg("DoQ connection dispatcher is no longer running".to_string()).into()),
+                connection.early_data_status(),
+            );
+        }
+
+        rx.recv().await.unwrap_or_else(|| {
+            (
+                Err(ProtoErrorKind::Msg("DoQ connection dispatcher closed without a response".to_string()).into()),
+                connection.early_data_status(),
+            )
+        })
+    }
+
+    /// Same as [`Self::inner_send`], but for AXFR/IXFR queries: the dispatcher is allowed to
+    /// deliver "one or more responses" on the channel (RFC 9250 section 4.2's "one or more
+    /// responses" per stream), so responses are read in a loop instead of expecting exactly one.
+    async fn inner_send_xfr(
+        connection: QuicClientConnection,
+        message: DnsRequest,
+    ) -> Result<
+        impl Stream<Item = (Result<DnsResponse, ProtoError>, EarlyDataStatus)>,
+        ProtoError,
+    > {
+        let (responses, rx) = mpsc::unbounded_channel();
+        connection
+            .dispatch
+            .send(DispatchRequest { message, responses })
+            .map_err(|_| {
+                ProtoError::from(ProtoErrorKind::Msg(
+                    "DoQ connection dispatcher is no longer running".to_string(),
+                ))
+            })?;
+
+        Ok(stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|item| (item, rx))
+        }))
+    }
+
+    /// Runs a single-response query: called by [`QuicClientConnection::drive`] on its own spawned
+    /// task per request, delivering the result to `request.responses`.
+    async fn dispatch_single(handle: ConnectionHandle, request: DispatchRequest) {
+        let result = match Self::open_and_exchange(&handle, request.message).await {
+            Ok((response, early_data_status)) => (Ok(response), early_data_status),
+            Err(e) => (Err(e), handle.early_data.status()),
+        };
+        let _ = request.responses.send(result);
+    }
+
+    /// Runs an AXFR/IXFR query: called by [`QuicClientConnection::drive`] on its own spawned task
+    /// per request, streaming every response to `request.responses` as it arrives.
+    async fn dispatch_xfr(handle: ConnectionHandle, request: DispatchRequest) {
+        match Self::open_and_exchange_xfr(&handle, request.message).await {
+            Ok(mut responses) => {
+                while let Some(item) = responses.next().await {
+                    if request.responses.send(item).is_err() {
+                        // The caller dropped its receiving end (e.g. the `DnsResponseStream` was
+                        // dropped mid-transfer); nothing is left to deliver the rest to.
+                        break;
+                    }
+                }
+            }
+            Err(e) => {
+                let status = handle.early_data.status();
+                let _ = request.responses.send((Err(e), status));
+            }
+        }
+    }
+
+    async fn open_and_exchange(
+        handle: &ConnectionHandle,
         message: DnsRequest,
-    ) -> Result<DnsResponse, ProtoError> {
-        let (send_stream, recv_stream) = connection.open_bi().await?;
+    ) -> Result<(DnsResponse, EarlyDataStatus), ProtoError> {
+        // Shed this query with DOQ_EXCESSIVE_LOAD rather than opening another stream if the
+        // connection already has as many queries in flight as it's configured to allow.
+        let _in_flight = handle.reserve()?;
+
+        // 0-RTT early data is replayable, so anything other than an idempotent query waits for
+        // the handshake to confirm before it's sent; see [`Self::is_early_data_safe`].
+        if !Self::is_early_data_safe(&message) {
+            handle.early_data.wait_confirmed().await;
+        }
+
+        // Captured right before the stream that actually carries this query is opened, so it
+        // reflects what applied to this specific request rather than the connection's status at
+        // some later, possibly different, point in time.
+        let early_data_status = handle.early_data.status();
+
+        let (send_stream, recv_stream) = handle.connection.open_bi().await?;
 
         // RFC: The mapping specified here requires that the client selects a separate
         //  QUIC stream for each query. The server then uses the same stream to provide all the response messages for that query.
-        let mut stream = QuicStream::new(send_stream, recv_stream);
+        let mut stream = QuicStream::new(send_stream, recv_stream)
+            .with_response_timeout(handle.limits.response_timeout);
 
         stream.send(message.into_parts().0).await?;
 
@@ -71,14 +438,114 @@ impl QuicClientStream {
         // and MUST indicate through the STREAM FIN mechanism that no further data will be sent on that stream.
         stream.finish().await?;
 
-        stream.receive().await
+        let response = stream.receive().await?;
+        Ok((response, early_data_status))
+    }
+
+    async fn open_and_exchange_xfr(
+        handle: &ConnectionHandle,
+        message: DnsRequest,
+    ) -> Result<
+        impl Stream<Item = (Result<DnsResponse, ProtoError>, EarlyDataStatus)>,
+        ProtoError,
+    > {
+        let in_flight = handle.reserve()?;
+
+        // AXFR/IXFR are still plain queries (just ones the server may answer with more than one
+        // message), so they're just as safe to replay as any other query.
+        if !Self::is_early_data_safe(&message) {
+            handle.early_data.wait_confirmed().await;
+        }
+
+        // Captured right before the stream is opened, same as [`Self::open_and_exchange`]: every
+        // response on this stream shares the same 0-RTT decision as the query that opened it, so
+        // it's attached to each one rather than left for the caller to re-derive from the
+        // connection's current (possibly since-changed) status.
+        let early_data_status = handle.early_data.status();
+
+        let (send_stream, recv_stream) = handle.connection.open_bi().await?;
+        let mut stream = QuicStream::new(send_stream, recv_stream)
+            .with_response_timeout(handle.limits.response_timeout);
+
+        stream.send(message.into_parts().0).await?;
+        stream.finish().await?;
+
+        // Keep the in-flight reservation held for as long as the caller is still consuming
+        // responses, not just until the query was sent, since an XFR exchange can stay open
+        // for many response messages.
+        Ok(stream.receive_all().map(move |item| {
+            let _in_flight = &in_flight;
+            (item, early_data_status)
+        }))
+    }
+
+    /// Only AXFR/IXFR queries are allowed more than one response per RFC 9250 section 4.2.
+    fn is_xfr(request: &DnsRequest) -> bool {
+        matches!(
+            request.queries().first().map(|query| query.query_type()),
+            Some(RecordType::AXFR) | Some(RecordType::IXFR)
+        )
+    }
+
+    /// Whether `request` is safe to send as 0-RTT early data, i.e. whether replaying it (an
+    /// on-path attacker can always replay the client's initial packet, see RFC 9001 section 8.1)
+    /// would be harmless. Plain queries, including AXFR/IXFR, are idempotent reads, so replaying
+    /// one changes nothing; DNS UPDATE mutates the zone, so it must only ever be sent once the
+    /// handshake is confirmed.
+    fn is_early_data_safe(request: &DnsRequest) -> bool {
+        request.op_code() == OpCode::Query
+    }
+
+    /// Returns whether this stream's connection currently used 0-RTT early data, and if so, how
+    /// that attempt has resolved so far. The decision is made once for the whole connection (RFC
+    /// 9250 section 4.2.1 notes a DoQ connection multiplexes many queries over one handshake),
+    /// but it's also time-varying (`Pending` transitions to `Accepted`/`Rejected` in the
+    /// background), so this is only an approximate, connection-wide snapshot; see
+    /// [`Self::send_message_with_early_data_status`] for the status that actually applied to a
+    /// specific response.
+    pub fn early_data_status(&self) -> EarlyDataStatus {
+        self.quic_connection.early_data_status()
+    }
+
+    /// Same as [`DnsRequestSender::send_message`], but also returns the [`EarlyDataStatus`]
+    /// captured at the moment this specific request was sent, so callers that want to apply their
+    /// own anti-replay policy can tell whether this particular response could have been replayed,
+    /// rather than having to infer it from [`Self::early_data_status`]'s time-varying,
+    /// connection-wide snapshot.
+    ///
+    /// Only supports single-response queries; AXFR/IXFR requests (see [`Self::is_xfr`]) can have
+    /// more than one response and must go through [`DnsRequestSender::send_message`] instead.
+    pub async fn send_message_with_early_data_status(
+        &mut self,
+        request: DnsRequest,
+    ) -> (Result<DnsResponse, ProtoError>, EarlyDataStatus) {
+        if self.is_shutdown {
+            panic!("can not send messages after stream is shutdown")
+        }
+
+        if Self::is_xfr(&request) {
+            let status = self.quic_connection.early_data_status();
+            return (
+                Err(ProtoErrorKind::Msg(
+                    "AXFR/IXFR queries can have more than one response; use \
+                     DnsRequestSender::send_message instead of \
+                     send_message_with_early_data_status"
+                        .to_string(),
+                )
+                .into()),
+                status,
+            );
+        }
+
+        Self::inner_send(self.quic_connection.clone(), request).await
     }
 }
 
 impl DnsRequestSender for QuicClientStream {
     /// The send loop for QUIC in DNS stipulates that a new QUIC "stream" should be opened and use for sending data.
     ///
-    /// It should be closed after receiving the response. TODO: AXFR/IXFR support...
+    /// It should be closed after receiving the response, or after the last response of an
+    /// AXFR/IXFR zone transfer (see [`Self::inner_send_xfr`]).
     ///
     /// ```text
     /// RFC 9250    DNS over Dedicated QUIC Connections
@@ -167,13 +634,25 @@ impl DnsRequestSender for QuicClientStream {
             panic!("can not send messages after stream is shutdown")
         }
 
-        Box::pin(Self::inner_send(self.quic_connection.clone(), request)).into()
+        if Self::is_xfr(&request) {
+            Box::pin(
+                Self::inner_send_xfr(self.quic_connection.clone(), request)
+                    .map_ok(|stream| stream.map(|(result, _early_data_status)| result))
+                    .try_flatten_stream(),
+            )
+            .into()
+        } else {
+            Box::pin(
+                Self::inner_send(self.quic_connection.clone(), request)
+                    .map(|(result, _early_data_status)| result),
+            )
+            .into()
+        }
     }
 
     fn shutdown(&mut self) {
         self.is_shutdown = true;
-        self.quic_connection
-            .close(DoqErrorCode::NoError.into(), b"Shutdown");
+        self.quic_connection.close(DoqErrorCode::NoError, b"Shutdown");
     }
 
     fn is_shutdown(&self) -> bool {
@@ -199,6 +678,9 @@ pub struct QuicClientStreamBuilder {
     crypto_config: Option<rustls::ClientConfig>,
     transport_config: Arc<TransportConfig>,
     bind_addr: Option<SocketAddr>,
+    limits: DoqLimits,
+    pool: Option<Arc<QuicClientPool>>,
+    early_data: bool,
 }
 
 impl QuicClientStreamBuilder {
@@ -214,6 +696,44 @@ impl QuicClientStreamBuilder {
         self
     }
 
+    /// Sets the maximum number of DoQ queries the resulting stream will allow in flight at once
+    /// on its connection before closing it with `DOQ_EXCESSIVE_LOAD` (defaults to
+    /// [`DEFAULT_MAX_QUERIES`]). Shorthand for setting just
+    /// [`DoqLimits::max_concurrent_streams`]; see [`Self::doq_limits`] to set both limits at once.
+    pub fn max_queries(mut self, max_queries: usize) -> Self {
+        self.limits.max_concurrent_streams = max_queries;
+        self
+    }
+
+    /// Sets the full set of [`DoqLimits`] (concurrent stream cap and per-stream response
+    /// timeout) the resulting stream enforces, superseding any prior [`Self::max_queries`] call.
+    pub fn doq_limits(mut self, limits: DoqLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Enables 0-RTT session resumption, letting the resulting stream send its first queries
+    /// before the QUIC handshake is confirmed instead of waiting a full round trip. Early data is
+    /// replayable (RFC 9001 section 8.1), so [`QuicClientStream`] only ever uses it for requests
+    /// it considers idempotent (see [`QuicClientStream::is_early_data_safe`]); DNS UPDATE and
+    /// other non-idempotent requests transparently wait for the handshake to confirm, regardless
+    /// of this setting. Has no effect unless the server previously issued a resumable session.
+    pub fn early_data(mut self) -> Self {
+        self.early_data = true;
+        self
+    }
+
+    /// Shares `pool`'s `Endpoint` and connection cache instead of opening a fresh `Endpoint` (and
+    /// UDP socket) and handshake for every call to [`Self::build`], so repeatedly resolving
+    /// through the same upstream reuses one DoQ connection (see [`QuicClientPool`]).
+    ///
+    /// Connections from the pool use the pool's own [`DoqLimits`]; this builder's
+    /// [`Self::doq_limits`]/[`Self::max_queries`] and [`Self::bind_addr`] are not applied to them.
+    pub fn with_pool(mut self, pool: Arc<QuicClientPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
     /// Creates a new QuicStream to the specified name_server
     ///
     /// # Arguments
@@ -221,7 +741,42 @@ impl QuicClientStreamBuilder {
     /// * `name_server` - IP and Port for the remote DNS resolver
     /// * `server_name` - The DNS name associated with a certificate
     pub fn build(self, name_server: SocketAddr, server_name: Arc<str>) -> QuicClientConnect {
-        QuicClientConnect(Box::pin(self.connect(name_server, server_name)) as _)
+        let fut = match self.pool.clone() {
+            Some(pool) => Box::pin(self.connect_via_pool(pool, name_server, server_name)) as _,
+            None => Box::pin(self.connect(name_server, server_name)) as _,
+        };
+        QuicClientConnect(fut)
+    }
+
+    async fn connect_via_pool(
+        self,
+        pool: Arc<QuicClientPool>,
+        name_server: SocketAddr,
+        server_name: Arc<str>,
+    ) -> Result<QuicClientStream, ProtoError> {
+        let mut crypto_config = match self.crypto_config {
+            Some(crypto_config) => crypto_config,
+            None => client_config()?,
+        };
+        if self.early_data {
+            crypto_config.enable_early_data = true;
+        }
+
+        let quic_connection = pool
+            .get_or_connect(
+                name_server,
+                server_name.clone(),
+                crypto_config,
+                self.transport_config,
+            )
+            .await?;
+
+        Ok(QuicClientStream {
+            quic_connection,
+            server_name,
+            name_server,
+            is_shutdown: false,
+        })
     }
 
     /// Create a QuicStream with existing connection
@@ -275,13 +830,16 @@ impl QuicClientStreamBuilder {
         server_name: Arc<str>,
     ) -> Result<QuicClientStream, ProtoError> {
         // ensure the ALPN protocol is set correctly
-        let crypto_config = if let Some(crypto_config) = self.crypto_config {
+        let mut crypto_config = if let Some(crypto_config) = self.crypto_config {
             crypto_config
         } else {
             client_config()?
         };
+        if self.early_data {
+            crypto_config.enable_early_data = true;
+        }
 
-        let quic_connection = connect_quic(
+        let (quic_connection, early_data) = connect_quic(
             name_server,
             server_name.clone(),
             quic_stream::DOQ_ALPN,
@@ -292,7 +850,7 @@ impl QuicClientStreamBuilder {
         .await?;
 
         Ok(QuicClientStream {
-            quic_connection,
+            quic_connection: QuicClientConnection::new(quic_connection, self.limits, early_data),
             server_name,
             name_server,
             is_shutdown: false,
@@ -300,14 +858,19 @@ impl QuicClientStreamBuilder {
     }
 }
 
+/// Connects to `addr`, returning the resulting connection along with its [`EarlyData`] status.
+/// When `crypto_config` enables early data, the connection is usable immediately via 0-RTT, but
+/// is only safe for idempotent requests (see [`QuicClientStream::is_early_data_safe`]) until its
+/// `EarlyData` reports the handshake as confirmed; non-idempotent requests, like DNS UPDATE, must
+/// wait for that regardless of how fast the connection otherwise became usable.
 pub(crate) async fn connect_quic(
     addr: SocketAddr,
     server_name: Arc<str>,
     protocol: &[u8],
     mut crypto_config: rustls::ClientConfig,
     transport_config: Arc<TransportConfig>,
-    mut endpoint: Endpoint,
-) -> Result<Connection, ProtoError> {
+    endpoint: Endpoint,
+) -> Result<(Connection, EarlyData), ProtoError> {
     if crypto_config.alpn_protocols.is_empty() {
         crypto_config.alpn_protocols = vec![protocol.to_vec()];
     }
@@ -316,18 +879,20 @@ pub(crate) async fn connect_quic(
     let mut client_config = ClientConfig::new(Arc::new(QuicClientConfig::try_from(crypto_config)?));
     client_config.transport_config(transport_config.clone());
 
-    endpoint.set_default_client_config(client_config);
-
-    let connecting = endpoint.connect(addr, &server_name)?;
-    // TODO: for Client/Dynamic update, don't use RTT, for queries, do use it.
+    // `Endpoint` is a cheap handle over state shared with every clone of it (including whatever
+    // `QuicClientPool` is holding onto), so this must hand `client_config` to `connect_with`
+    // rather than going through `set_default_client_config` + `connect`: the latter pair would
+    // briefly mutate that shared default, racing with any other connection attempt running
+    // concurrently on the same endpoint.
+    let connecting = endpoint.connect_with(client_config, addr, &server_name)?;
 
     Ok(if early_data_enabled {
         match connecting.into_0rtt() {
-            Ok((new_connection, _)) => new_connection,
-            Err(connecting) => connect_with_timeout(connecting).await?,
+            Ok((new_connection, accepted)) => (new_connection, EarlyData::pending(accepted)),
+            Err(connecting) => (connect_with_timeout(connecting).await?, EarlyData::confirmed()),
         }
     } else {
-        connect_with_timeout(connecting).await?
+        (connect_with_timeout(connecting).await?, EarlyData::confirmed())
     })
 }
 
@@ -352,6 +917,9 @@ impl Default for QuicClientStreamBuilder {
             crypto_config: None,
             transport_config: Arc::new(transport_config),
             bind_addr: None,
+            limits: DoqLimits::default(),
+            pool: None,
+            early_data: false,
         }
     }
 }
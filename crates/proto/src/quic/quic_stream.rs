@@ -5,8 +5,12 @@
 // https://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use std::time::Duration;
+
 use bytes::{Bytes, BytesMut};
-use quinn::{RecvStream, SendStream, VarInt};
+use futures_util::stream::{self, Stream};
+use quinn::{ReadExactError, RecvStream, SendStream, VarInt};
+use tokio::time::timeout;
 use tracing::debug;
 
 use crate::{
@@ -121,6 +125,8 @@ impl From<VarInt> for DoqErrorCode {
 pub struct QuicStream {
     send_stream: SendStream,
     receive_stream: RecvStream,
+    completed: bool,
+    response_timeout: Option<Duration>,
 }
 
 impl QuicStream {
@@ -128,9 +134,19 @@ impl QuicStream {
         Self {
             send_stream,
             receive_stream,
+            completed: false,
+            response_timeout: None,
         }
     }
 
+    /// Bounds every subsequent read on the receive side by `timeout`, so a "dangling" stream —
+    /// one whose response or STREAM FIN never arrives (RFC 9250 section 4.2) — fails instead of
+    /// hanging forever.
+    pub(crate) fn with_response_timeout(mut self, timeout: Duration) -> Self {
+        self.response_timeout = Some(timeout);
+        self
+    }
+
     /// Send the DNS message to the other side
     pub async fn send(&mut self, mut message: Message) -> Result<(), ProtoError> {
         // RFC: When sending queries over a QUIC connection, the DNS Message ID MUST be set to 0.
@@ -170,27 +186,84 @@ impl QuicStream {
     }
 
     /// Receive a single packet
+    ///
+    /// Per RFC 9250 section 4.2, only zone transfers are allowed more than one response on a
+    /// stream; if the peer sends a second message here, the stream is reset with
+    /// `DOQ_PROTOCOL_ERROR`. XFR queries should use [`Self::receive_all`] instead.
     pub async fn receive(&mut self) -> Result<DnsResponse, ProtoError> {
-        let bytes = self.receive_bytes().await?;
-        let message = Message::from_vec(&bytes)?;
+        let bytes = self.receive_bytes().await;
 
-        // assert that the message id is 0, this is a bad dns-over-quic packet if not
-        if message.id() != 0 {
+        // Mark the exchange as having run to completion regardless of outcome, once the read has
+        // resolved, so `Drop` can tell a stream that finished (even with an error) apart from one
+        // abandoned mid-flight, e.g. because the caller's future was dropped due to a timeout.
+        self.completed = true;
+
+        let response = self.decode(bytes?)?;
+
+        if self.read_one().await?.is_some() {
             if let Err(error) = self.reset(DoqErrorCode::ProtocolError) {
                 debug!(%error, "stream already closed");
             }
-            return Err(ProtoErrorKind::QuicMessageIdNot0(message.id()).into());
+            return Err(ProtoErrorKind::Msg(
+                "received more than one response for a non-XFR DoQ query".to_string(),
+            )
+            .into());
         }
 
-        DnsResponse::from_buffer(bytes.to_vec())
+        Ok(response)
     }
 
     // TODO: we should change the protocol handlers to work with Messages since some require things like 0 for the Message ID.
     /// Receive a single packet as raw bytes
     pub async fn receive_bytes(&mut self) -> Result<BytesMut, ProtoError> {
+        self.read_one().await?.ok_or_else(|| {
+            ProtoErrorKind::Msg("DoQ stream finished before a response was received".to_string())
+                .into()
+        })
+    }
+
+    /// Receives every response on this stream until the peer finishes it, for AXFR/IXFR zone
+    /// transfers, where RFC 9250 section 4.2 allows the server "one or more responses" on the
+    /// same stream. Only XFR queries may use this; [`Self::receive`] resets non-XFR transactions
+    /// that get more than one message with `DOQ_PROTOCOL_ERROR`.
+    pub fn receive_all(self) -> impl Stream<Item = Result<DnsResponse, ProtoError>> {
+        stream::unfold(Some(self), |state| async move {
+            let mut stream = state?;
+            match stream.read_one().await {
+                Ok(Some(bytes)) => match stream.decode(bytes) {
+                    Ok(response) => Some((Ok(response), Some(stream))),
+                    Err(e) => Some((Err(e), None)),
+                },
+                Ok(None) => {
+                    stream.completed = true;
+                    None
+                }
+                Err(e) => Some((Err(e), None)),
+            }
+        })
+    }
+
+    /// Reads the next length-prefixed message on this stream, or `None` if the peer has finished
+    /// the stream cleanly with no partial message pending, as happens after the last response of
+    /// a multi-message AXFR/IXFR transfer.
+    async fn read_one(&mut self) -> Result<Option<BytesMut>, ProtoError> {
         // following above, the data should be first the length, followed by the message(s)
         let mut len = [0u8; 2];
-        self.receive_stream.read_exact(&mut len).await?;
+        match self.bounded_read_exact(&mut len).await? {
+            Ok(()) => {}
+            // The peer closed the stream (STREAM FIN) exactly on a message boundary: a clean end
+            // of transfer, not an error.
+            Err(ReadExactError::FinishedEarly(0)) => return Ok(None),
+            // The peer closed the stream partway through the 2-octet length prefix: a truncated
+            // message, which is a protocol violation rather than the end of a transfer, so the
+            // peer is told as much instead of just silently tearing down the stream locally.
+            Err(e) => {
+                if let Err(error) = self.reset(DoqErrorCode::ProtocolError) {
+                    debug!(%error, "stream already closed");
+                }
+                return Err(e.into());
+            }
+        }
         let len = u16::from_be_bytes(len) as usize;
 
         // RFC: DoQ queries and responses are sent on QUIC streams, which in theory can carry up to
@@ -200,7 +273,7 @@ impl QuicStream {
         // "application/dns-message" for DoH [RFC8484].  DoQ enforces the same restriction.
         let mut bytes = BytesMut::with_capacity(len);
         bytes.resize(len, 0);
-        if let Err(e) = self.receive_stream.read_exact(&mut bytes[..len]).await {
+        if let Err(e) = self.bounded_read_exact(&mut bytes[..len]).await? {
             debug!("received bad packet len: {} bytes: {:?}", len, bytes);
 
             if let Err(error) = self.reset(DoqErrorCode::ProtocolError) {
@@ -210,7 +283,41 @@ impl QuicStream {
         }
 
         debug!("received packet len: {} bytes: {:x?}", len, bytes);
-        Ok(bytes)
+        Ok(Some(bytes))
+    }
+
+    /// Runs `self.receive_stream.read_exact(buf)`, bounded by [`Self::response_timeout`] if one
+    /// was set. A timeout is surfaced as a dedicated `DOQ_DANGLING_STREAM` error rather than the
+    /// inner `Result`, distinguishing "gave up waiting" from a `ReadExactError` the peer caused.
+    async fn bounded_read_exact(
+        &mut self,
+        buf: &mut [u8],
+    ) -> Result<Result<(), ReadExactError>, ProtoError> {
+        match self.response_timeout {
+            Some(duration) => timeout(duration, self.receive_stream.read_exact(buf))
+                .await
+                .map_err(|_| {
+                    ProtoErrorKind::Msg(format!(
+                        "DOQ_DANGLING_STREAM: no response within {duration:?}, see RFC 9250 section 4.2"
+                    ))
+                    .into()
+                }),
+            None => Ok(self.receive_stream.read_exact(buf).await),
+        }
+    }
+
+    fn decode(&mut self, bytes: BytesMut) -> Result<DnsResponse, ProtoError> {
+        let message = Message::from_vec(&bytes)?;
+
+        // assert that the message id is 0, this is a bad dns-over-quic packet if not
+        if message.id() != 0 {
+            if let Err(error) = self.reset(DoqErrorCode::ProtocolError) {
+                debug!(%error, "stream already closed");
+            }
+            return Err(ProtoErrorKind::QuicMessageIdNot0(message.id()).into());
+        }
+
+        DnsResponse::from_buffer(bytes.to_vec())
     }
 
     /// Reset the sending stream due to some error
@@ -227,3 +334,18 @@ impl QuicStream {
             .map_err(|_| ProtoError::from(ProtoErrorKind::QuinnUnknownStreamError))
     }
 }
+
+impl Drop for QuicStream {
+    /// Signals `DOQ_REQUEST_CANCELLED` if this stream is being torn down before its exchange ran
+    /// to completion, e.g. because the future awaiting the response was dropped due to a timeout.
+    /// This tells the server to stop processing immediately rather than leaving the stream
+    /// "dangling" until its own implementation-defined timeout (RFC 9250 section 4.2).
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+
+        let _ = self.reset(DoqErrorCode::RequestCancelled);
+        let _ = self.stop(DoqErrorCode::RequestCancelled);
+    }
+}
@@ -0,0 +1,173 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Configuration for a resolver: the nameservers and search domains to query ([`ResolverConfig`])
+//! and the options that govern how queries are made ([`ResolverOpts`]).
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::lookup_ip::SortlistEntry;
+use crate::proto::rr::Name;
+
+/// The protocol(s) to use when contacting a nameserver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// UDP, falling back to TCP on truncation, per RFC 1035.
+    Udp,
+    /// TCP only.
+    Tcp,
+}
+
+/// Configuration for an upstream nameserver: its address and how to reach it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NameServerConfig {
+    /// The address of the nameserver.
+    pub ip: IpAddr,
+    /// The protocol to use to contact this nameserver.
+    pub protocol: Protocol,
+    /// Whether a negative response (e.g. `NXDOMAIN`) from this nameserver should be trusted and
+    /// cached, or treated as suspect and retried against another nameserver.
+    pub trust_negative_responses: bool,
+}
+
+impl NameServerConfig {
+    /// Creates a nameserver config that queries `ip` over UDP, falling back to TCP on truncation.
+    pub fn udp_and_tcp(ip: IpAddr) -> Self {
+        Self {
+            ip,
+            protocol: Protocol::Udp,
+            trust_negative_responses: true,
+        }
+    }
+}
+
+/// The set of nameservers and search domains a resolver should use.
+#[derive(Debug, Clone, Default)]
+pub struct ResolverConfig {
+    domain: Option<Name>,
+    search: Vec<Name>,
+    name_servers: Vec<NameServerConfig>,
+}
+
+impl ResolverConfig {
+    /// Builds a config from an already-parsed domain, search list, and nameserver list.
+    pub fn from_parts(
+        domain: Option<Name>,
+        search: Vec<Name>,
+        name_servers: Vec<NameServerConfig>,
+    ) -> Self {
+        Self {
+            domain,
+            search,
+            name_servers,
+        }
+    }
+
+    /// The system domain name, if one was configured.
+    pub fn domain(&self) -> Option<&Name> {
+        self.domain.as_ref()
+    }
+
+    /// Sets the system domain name.
+    pub fn set_domain(&mut self, domain: Name) {
+        self.domain = Some(domain);
+    }
+
+    /// The configured search list, tried in order when resolving a relative name.
+    pub fn search(&self) -> &[Name] {
+        &self.search
+    }
+
+    /// Appends a domain to the search list.
+    pub fn add_search(&mut self, search: Name) {
+        self.search.push(search);
+    }
+
+    /// The configured upstream nameservers.
+    pub fn name_servers(&self) -> &[NameServerConfig] {
+        &self.name_servers
+    }
+
+    /// Appends a nameserver to the list this config will query.
+    pub fn add_name_server(&mut self, name_server: NameServerConfig) {
+        self.name_servers.push(name_server);
+    }
+}
+
+/// Which address families to query, and in what order/combination, when resolving a name to IP
+/// addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LookupIpStrategy {
+    /// Query for A (IPv4) records only.
+    Ipv4Only,
+    /// Query for AAAA (IPv6) records only.
+    Ipv6Only,
+    /// Query both A and AAAA records concurrently, the default strategy.
+    #[default]
+    Ipv4AndIpv6,
+    /// Query AAAA first, falling back to A if no AAAA records are found.
+    Ipv6thenIpv4,
+    /// Query A first, falling back to AAAA if no A records are found.
+    Ipv4thenIpv6,
+}
+
+/// Options that control how a [`crate::lookup_ip::LookupIpFuture`] (or a resolver built on top of
+/// it) makes and interprets queries, most of which mirror the like-named `resolv.conf(5)`
+/// directives.
+#[derive(Debug, Clone)]
+pub struct ResolverOpts {
+    /// Names with fewer than this many dots are tried as relative (search-list-qualified) names
+    /// before being tried as absolute names.
+    pub ndots: usize,
+    /// How long to wait for a response before giving up on a nameserver.
+    pub timeout: Duration,
+    /// How many times to retry a query against a nameserver before giving up.
+    pub attempts: usize,
+    /// Whether to send an `OPT` record (EDNS0) with queries.
+    pub edns0: bool,
+    /// Whether to rotate through the configured nameservers round-robin, instead of always
+    /// querying them in listed order.
+    pub rotate: bool,
+    /// Whether to use only TCP, never UDP, for queries.
+    pub use_vc: bool,
+    /// Whether to avoid reusing a single socket for both the A and AAAA queries of a lookup.
+    pub single_request: bool,
+    /// Like [`Self::single_request`], but additionally closes and reopens the socket between the
+    /// two queries.
+    pub single_request_reopen: bool,
+    /// Whether to skip validating that queried names and returned record names are
+    /// RFC 952/RFC 1123 conformant hostnames.
+    pub no_check_names: bool,
+    /// Whether to trust the `AD` (authentic data) bit on responses from the configured
+    /// nameservers without performing DNSSEC validation locally.
+    pub trust_ad: bool,
+    /// A `sortlist` (see `resolv.conf(5)`) used to reorder resolved addresses by which configured
+    /// network they fall into.
+    pub sortlist: Vec<SortlistEntry>,
+    /// Which address families to query, and in what order/combination.
+    pub ip_strategy: LookupIpStrategy,
+}
+
+impl Default for ResolverOpts {
+    fn default() -> Self {
+        Self {
+            ndots: 1,
+            timeout: Duration::from_secs(5),
+            attempts: 2,
+            edns0: false,
+            rotate: false,
+            use_vc: false,
+            single_request: false,
+            single_request_reopen: false,
+            no_check_names: false,
+            trust_ad: false,
+            sortlist: vec![],
+            ip_strategy: LookupIpStrategy::default(),
+        }
+    }
+}
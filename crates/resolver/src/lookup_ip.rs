@@ -9,13 +9,16 @@
 //!
 //! At it's heart LookupIp uses Lookup for performing all lookups. It is unlike other standard lookups in that there are customizations around A and AAAA resolutions.
 
+use std::collections::VecDeque;
 use std::future::Future;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::task::{Context, Poll};
 use std::time::Instant;
 
+use futures_util::stream::{self, Stream, StreamExt};
 use futures_util::{FutureExt, future, future::Either};
 use tracing::debug;
 
@@ -24,69 +27,356 @@ use crate::proto::rr::{Name, RData, Record, RecordType};
 use crate::proto::xfer::{DnsHandle, DnsRequestOptions};
 
 use crate::caching_client::CachingClient;
-use crate::config::LookupIpStrategy;
+use crate::config::{LookupIpStrategy, NameServerConfig};
 use crate::dns_lru::MAX_TTL;
 use crate::error::*;
 use crate::hosts::Hosts;
 use crate::lookup::{Lookup, LookupIntoIter, LookupIter};
 
+/// A single entry of a glibc-style `sortlist` (see `resolv.conf(5)`): a network address plus its
+/// netmask, used to bucket candidate addresses by which configured network they fall into.
+#[derive(Debug, Clone, Copy)]
+pub struct SortlistEntry {
+    network: IpAddr,
+    netmask: IpAddr,
+}
+
+impl SortlistEntry {
+    /// Construct a new sortlist entry from a network address and its netmask.
+    pub fn new(network: IpAddr, netmask: IpAddr) -> Self {
+        Self { network, netmask }
+    }
+
+    fn matches(&self, addr: IpAddr) -> bool {
+        match (self.network, self.netmask, to_ipv4_mapped(addr)) {
+            (IpAddr::V4(net), IpAddr::V4(mask), IpAddr::V4(addr)) => {
+                (u32::from(addr) & u32::from(mask)) == (u32::from(net) & u32::from(mask))
+            }
+            (IpAddr::V6(net), IpAddr::V6(mask), IpAddr::V6(addr)) => {
+                (u128::from(addr) & u128::from(mask)) == (u128::from(net) & u128::from(mask))
+            }
+            // An IPv4 network never matches an IPv6 address other than an IPv4-mapped one, and an
+            // IPv6 network never matches an IPv4 or IPv4-mapped address.
+            _ => false,
+        }
+    }
+}
+
+fn to_ipv4_mapped(addr: IpAddr) -> IpAddr {
+    match addr {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(IpAddr::V6(v6), IpAddr::V4),
+        addr => addr,
+    }
+}
+
+/// Reorders `addrs` so that addresses falling in an earlier-listed `sortlist` network sort before
+/// addresses falling in a later one, with addresses matching no network sorted last.
+///
+/// This mirrors glibc's use of `resolv.conf`'s `sortlist` directive to reorder returned addresses.
+/// An empty `sortlist` is a no-op. The sort is stable, so addresses within the same bucket (and
+/// the relative order of the servers that returned them) is preserved.
+pub(crate) fn sort_by_sortlist(mut addrs: Vec<IpAddr>, sortlist: &[SortlistEntry]) -> Vec<IpAddr> {
+    if sortlist.is_empty() {
+        return addrs;
+    }
+
+    addrs.sort_by_key(|addr| {
+        sortlist
+            .iter()
+            .position(|entry| entry.matches(*addr))
+            .unwrap_or(sortlist.len())
+    });
+    addrs
+}
+
+/// Interleaves `addrs` by address family, per the "Sorting" guidance of
+/// [RFC 8305](https://www.rfc-editor.org/rfc/rfc8305) (Happy Eyeballs): rather than returning
+/// every address of one family before any address of the other, alternate between families so a
+/// caller that tries addresses in order attempts both families early instead of exhausting a
+/// family that may be unreachable before ever trying the other.
+///
+/// The family of the first address in `addrs` is preserved as the starting family, since that is
+/// normally the family that answered first (see [`ipv4_and_ipv6`]). Each family's relative order
+/// is otherwise preserved, and once one family is exhausted the remaining addresses of the other
+/// are appended as-is.
+pub(crate) fn interleave_by_family(addrs: Vec<IpAddr>) -> Vec<IpAddr> {
+    let mut first = VecDeque::new();
+    let mut second = VecDeque::new();
+
+    let mut first_family = None;
+    for addr in addrs {
+        let family_is_first = *first_family.get_or_insert(matches!(addr, IpAddr::V4(_)));
+        if matches!(addr, IpAddr::V4(_)) == family_is_first {
+            first.push_back(addr);
+        } else {
+            second.push_back(addr);
+        }
+    }
+
+    let mut interleaved = Vec::with_capacity(first.len() + second.len());
+    loop {
+        match (first.pop_front(), second.pop_front()) {
+            (Some(a), Some(b)) => {
+                interleaved.push(a);
+                interleaved.push(b);
+            }
+            (Some(a), None) => {
+                interleaved.push(a);
+                interleaved.extend(first);
+                break;
+            }
+            (None, Some(b)) => {
+                interleaved.push(b);
+                interleaved.extend(second);
+                break;
+            }
+            (None, None) => break,
+        }
+    }
+    interleaved
+}
+
+/// Reorders `addrs` per the destination address selection rules of
+/// [RFC 6724](https://www.rfc-editor.org/rfc/rfc6724), restricted to the rules that don't require
+/// an actual routing-table lookup:
+///
+/// * Rule 2 (prefer a usable source address) — a destination for which no local address in
+///   `sources` shares its family sorts after one that has a candidate source.
+/// * Rule 6 (prefer higher precedence) — classifies each destination using the default policy
+///   table (`::1/128` → 50, IPv4-mapped → 35, `2002::/16` → 30, `2001::/32` → 5, `fc00::/7` → 3,
+///   everything else → 40).
+/// * Rule 8 (prefer smaller scope) — compares the scope of the matched source address (or, with
+///   no source table, the destination's own scope) and prefers the smaller one.
+/// * Rule 9 (longest matching prefix) — IPv6 ties are broken by the longest common prefix with
+///   the paired source address.
+///
+/// An empty `sources` table disables rules 2 and 9 and falls back to precedence-and-scope-only
+/// ordering, per rule 8 using each destination's own scope.
+pub(crate) fn sort_by_rfc6724(mut addrs: Vec<IpAddr>, sources: &[IpAddr]) -> Vec<IpAddr> {
+    addrs.sort_by(|a, b| rfc6724_cmp(*a, *b, sources));
+    addrs
+}
+
+fn rfc6724_cmp(a: IpAddr, b: IpAddr, sources: &[IpAddr]) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    let source_a = matching_source(a, sources);
+    let source_b = matching_source(b, sources);
+
+    // Rule 2: prefer a destination for which a usable source address exists.
+    if !sources.is_empty() {
+        match (source_a.is_some(), source_b.is_some()) {
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            _ => {}
+        }
+    }
+
+    // Rule 6: prefer higher precedence.
+    match precedence(b).cmp(&precedence(a)) {
+        Ordering::Equal => {}
+        other => return other,
+    }
+
+    // Rule 8: prefer smaller scope, using the matched source's scope when we have one.
+    let scope_a = source_a.map_or_else(|| scope(a), scope);
+    let scope_b = source_b.map_or_else(|| scope(b), scope);
+    match scope_a.cmp(&scope_b) {
+        Ordering::Equal => {}
+        other => return other,
+    }
+
+    // Rule 9: longest matching prefix with the paired source, IPv6 only.
+    if let (Some(sa), Some(sb)) = (source_a, source_b) {
+        if matches!((a, b), (IpAddr::V6(_), IpAddr::V6(_))) {
+            return common_prefix_len(b, sb).cmp(&common_prefix_len(a, sa));
+        }
+    }
+
+    Ordering::Equal
+}
+
+/// Picks the local source address in `sources` most likely to be used for `addr`: the same-family
+/// address whose scope is closest to `addr`'s own scope.
+fn matching_source(addr: IpAddr, sources: &[IpAddr]) -> Option<IpAddr> {
+    sources
+        .iter()
+        .copied()
+        .filter(|src| same_family(*src, addr))
+        .min_by_key(|src| scope(*src).abs_diff(scope(addr)))
+}
+
+fn same_family(a: IpAddr, b: IpAddr) -> bool {
+    matches!(
+        (a, b),
+        (IpAddr::V4(_), IpAddr::V4(_)) | (IpAddr::V6(_), IpAddr::V6(_))
+    )
+}
+
+/// RFC 6724 address scope: 2 (link-local), 5 (site-local), or 14 (global).
+fn scope(addr: IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(v4) if v4.is_loopback() || v4.is_link_local() => 2,
+        IpAddr::V4(v4) if v4.is_private() => 5,
+        IpAddr::V4(_) => 14,
+        IpAddr::V6(v6) if v6.is_loopback() || (v6.segments()[0] & 0xffc0) == 0xfe80 => 2,
+        IpAddr::V6(v6) if (v6.segments()[0] & 0xfe00) == 0xfec0 => 5,
+        IpAddr::V6(_) => 14,
+    }
+}
+
+/// RFC 6724 rule 6 policy-table precedence, classifying `addr` via its IPv4-mapped IPv6 form.
+fn precedence(addr: IpAddr) -> u8 {
+    let mapped = match addr {
+        IpAddr::V4(v4) => v4.to_ipv6_mapped(),
+        IpAddr::V6(v6) => v6,
+    };
+
+    if mapped == Ipv6Addr::LOCALHOST {
+        50
+    } else if mapped.to_ipv4_mapped().is_some() {
+        35
+    } else if mapped.segments()[0] == 0x2002 {
+        30
+    } else if mapped.segments()[0] == 0x2001 && mapped.segments()[1] == 0 {
+        5
+    } else if (mapped.segments()[0] & 0xfe00) == 0xfc00 {
+        3
+    } else {
+        40
+    }
+}
+
+/// Longest matching prefix length in bits, IPv6 only (0 for any other combination).
+fn common_prefix_len(a: IpAddr, b: IpAddr) -> u32 {
+    match (a, b) {
+        (IpAddr::V6(a), IpAddr::V6(b)) => (u128::from(a) ^ u128::from(b)).leading_zeros(),
+        _ => 0,
+    }
+}
+
 /// Result of a DNS query when querying for A or AAAA records.
 ///
 /// When resolving IP records, there can be many IPs that match a given name. A consumer of this should expect that there are more than a single address potentially returned. Generally there are multiple IPs stored for a given service in DNS so that there is a form of high availability offered for a given name. The service implementation is responsible for the semantics around which IP should be used and when, but in general if a connection fails to one, the next in the list should be attempted.
 #[derive(Debug, Clone)]
-pub struct LookupIp(Lookup);
+pub struct LookupIp {
+    lookup: Lookup,
+    sortlist: Arc<[SortlistEntry]>,
+    interleave: bool,
+    rfc6724_sources: Option<Arc<[IpAddr]>>,
+}
 
 impl LookupIp {
+    /// Associates a parsed `sortlist` (from `ResolverOpts`), Happy Eyeballs address-family
+    /// interleaving (see [`interleave_by_family`]), and RFC 6724 destination address selection
+    /// (see [`sort_by_rfc6724`]) with a `Lookup` so that `iter()`/`into_iter()` return addresses
+    /// in the right order.
+    ///
+    /// At most one ordering is applied: an explicit `sortlist` takes precedence, since it
+    /// reflects an operator's explicit preference, followed by RFC 6724 ordering when
+    /// `rfc6724_sources` is `Some` (an empty slice still enables it, falling back to
+    /// precedence-and-scope-only ordering), followed by interleaving.
+    pub(crate) fn from_parts(
+        lookup: Lookup,
+        sortlist: Arc<[SortlistEntry]>,
+        interleave: bool,
+        rfc6724_sources: Option<Arc<[IpAddr]>>,
+    ) -> Self {
+        Self {
+            lookup,
+            sortlist,
+            interleave,
+            rfc6724_sources,
+        }
+    }
+
     /// Returns an iterator over the response records.
     ///
-    /// Only IP records will be returned, either A or AAAA record types.
+    /// Only IP records will be returned, either A or AAAA record types. Addresses are ordered
+    /// according to whichever of `sortlist`, RFC 6724 destination address selection, or Happy
+    /// Eyeballs interleaving is configured (see [`from_parts`](Self::from_parts) for precedence);
+    /// failing all three, raw record order is preserved.
     pub fn iter(&self) -> LookupIpIter<'_> {
-        LookupIpIter(self.0.iter())
+        if !self.sortlist.is_empty() {
+            let addrs = self.lookup.iter().filter_map(rdata_to_ip).collect();
+            let addrs = sort_by_sortlist(addrs, &self.sortlist);
+            return LookupIpIter(LookupIpIterInner::Sorted(addrs.into_iter()));
+        }
+
+        if let Some(sources) = &self.rfc6724_sources {
+            let addrs = self.lookup.iter().filter_map(rdata_to_ip).collect();
+            let addrs = sort_by_rfc6724(addrs, sources);
+            return LookupIpIter(LookupIpIterInner::Sorted(addrs.into_iter()));
+        }
+
+        if self.interleave {
+            let addrs = self.lookup.iter().filter_map(rdata_to_ip).collect();
+            let addrs = interleave_by_family(addrs);
+            return LookupIpIter(LookupIpIterInner::Sorted(addrs.into_iter()));
+        }
+
+        LookupIpIter(LookupIpIterInner::Unsorted(self.lookup.iter()))
     }
 
     /// Returns a reference to the `Query` that was used to produce this result.
     pub fn query(&self) -> &Query {
-        self.0.query()
+        self.lookup.query()
     }
 
     /// Returns the `Instant` at which this lookup is no longer valid.
     pub fn valid_until(&self) -> Instant {
-        self.0.valid_until()
+        self.lookup.valid_until()
     }
 
     /// Return a reference to the inner lookup
     ///
     /// This can be useful for getting all records from the request
     pub fn as_lookup(&self) -> &Lookup {
-        &self.0
+        &self.lookup
+    }
+}
+
+fn rdata_to_ip(rdata: &RData) -> Option<IpAddr> {
+    match rdata {
+        RData::A(ip) => Some(IpAddr::from(Ipv4Addr::from(*ip))),
+        RData::AAAA(ip) => Some(IpAddr::from(Ipv6Addr::from(*ip))),
+        _ => None,
     }
 }
 
 impl From<Lookup> for LookupIp {
     fn from(lookup: Lookup) -> Self {
-        Self(lookup)
+        Self {
+            lookup,
+            sortlist: Arc::from([]),
+            interleave: false,
+            rfc6724_sources: None,
+        }
     }
 }
 
 impl From<LookupIp> for Lookup {
     fn from(lookup: LookupIp) -> Self {
-        lookup.0
+        lookup.lookup
     }
 }
 
+enum LookupIpIterInner<'i> {
+    Unsorted(LookupIter<'i>),
+    Sorted(std::vec::IntoIter<IpAddr>),
+}
+
 /// Borrowed view of set of IPs returned from a LookupIp
-pub struct LookupIpIter<'i>(pub(crate) LookupIter<'i>);
+pub struct LookupIpIter<'i>(LookupIpIterInner<'i>);
 
 impl Iterator for LookupIpIter<'_> {
     type Item = IpAddr;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let iter: &mut _ = &mut self.0;
-        iter.find_map(|rdata| match rdata {
-            RData::A(ip) => Some(IpAddr::from(Ipv4Addr::from(*ip))),
-            RData::AAAA(ip) => Some(IpAddr::from(Ipv6Addr::from(*ip))),
-            _ => None,
-        })
+        match &mut self.0 {
+            LookupIpIterInner::Unsorted(iter) => iter.find_map(rdata_to_ip),
+            LookupIpIterInner::Sorted(iter) => iter.next(),
+        }
     }
 }
 
@@ -96,23 +386,48 @@ impl IntoIterator for LookupIp {
 
     /// This is not a free conversion, because the `RData`s are cloned.
     fn into_iter(self) -> Self::IntoIter {
-        LookupIpIntoIter(self.0.into_iter())
+        if !self.sortlist.is_empty() {
+            let addrs = self.lookup.iter().filter_map(rdata_to_ip).collect();
+            let addrs = sort_by_sortlist(addrs, &self.sortlist);
+            return LookupIpIntoIter(LookupIpIntoIterInner::Sorted(addrs.into_iter()));
+        }
+
+        if let Some(sources) = &self.rfc6724_sources {
+            let addrs = self.lookup.iter().filter_map(rdata_to_ip).collect();
+            let addrs = sort_by_rfc6724(addrs, sources);
+            return LookupIpIntoIter(LookupIpIntoIterInner::Sorted(addrs.into_iter()));
+        }
+
+        if self.interleave {
+            let addrs = self.lookup.iter().filter_map(rdata_to_ip).collect();
+            let addrs = interleave_by_family(addrs);
+            return LookupIpIntoIter(LookupIpIntoIterInner::Sorted(addrs.into_iter()));
+        }
+
+        LookupIpIntoIter(LookupIpIntoIterInner::Unsorted(self.lookup.into_iter()))
     }
 }
 
+enum LookupIpIntoIterInner {
+    Unsorted(LookupIntoIter),
+    Sorted(std::vec::IntoIter<IpAddr>),
+}
+
 /// Borrowed view of set of RDatas returned from a Lookup
-pub struct LookupIpIntoIter(LookupIntoIter);
+pub struct LookupIpIntoIter(LookupIpIntoIterInner);
 
 impl Iterator for LookupIpIntoIter {
     type Item = IpAddr;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let iter: &mut _ = &mut self.0;
-        iter.find_map(|rdata| match rdata {
-            RData::A(ip) => Some(IpAddr::from(Ipv4Addr::from(ip))),
-            RData::AAAA(ip) => Some(IpAddr::from(Ipv6Addr::from(ip))),
-            _ => None,
-        })
+        match &mut self.0 {
+            LookupIpIntoIterInner::Unsorted(iter) => iter.find_map(|rdata| match rdata {
+                RData::A(ip) => Some(IpAddr::from(Ipv4Addr::from(ip))),
+                RData::AAAA(ip) => Some(IpAddr::from(Ipv6Addr::from(ip))),
+                _ => None,
+            }),
+            LookupIpIntoIterInner::Sorted(iter) => iter.next(),
+        }
     }
 }
 
@@ -130,6 +445,9 @@ where
     query: Pin<Box<dyn Future<Output = Result<Lookup, ResolveError>> + Send>>,
     hosts: Option<Arc<Hosts>>,
     finally_ip_addr: Option<RData>,
+    sortlist: Arc<[SortlistEntry]>,
+    interleave: bool,
+    rfc6724_sources: Option<Arc<[IpAddr]>>,
 }
 
 impl<C> Future for LookupIpFuture<C>
@@ -175,14 +493,24 @@ where
                     // we'll return it.
                     let record = Record::from_rdata(Name::new(), MAX_TTL, ip_addr);
                     let lookup = Lookup::new_with_max_ttl(Query::new(), Arc::from([record]));
-                    return Poll::Ready(Ok(lookup.into()));
+                    return Poll::Ready(Ok(LookupIp::from_parts(
+                        lookup,
+                        self.sortlist.clone(),
+                        self.interleave,
+                        self.rfc6724_sources.clone(),
+                    )));
                 }
             };
 
             // If we didn't have to retry the query, or we weren't able to
             // retry because we've exhausted the names to search and have no
             // fallback IP address, return the current query.
-            return query.map(|f| f.map(LookupIp::from));
+            let sortlist = self.sortlist.clone();
+            let interleave = self.interleave;
+            let rfc6724_sources = self.rfc6724_sources.clone();
+            return query.map(|f| {
+                f.map(|lookup| LookupIp::from_parts(lookup, sortlist, interleave, rfc6724_sources))
+            });
             // If we skipped retrying the  query, this will return the
             // successful lookup, otherwise, if the retry failed, this will
             // return the last  query result --- either an empty lookup or the
@@ -202,6 +530,18 @@ where
     /// * `names` - a set of DNS names to attempt to resolve, they will be attempted in queue order, i.e. the first is `names.pop()`. Upon each failure, the next will be attempted.
     /// * `strategy` - the lookup IP strategy to use
     /// * `client_cache` - cache with a connection to use for performing all lookups
+    /// * `sortlist` - glibc-style `resolv.conf` sortlist used to order the returned addresses;
+    ///   an empty slice leaves the server-returned order untouched
+    /// * `rfc6724_sources` - local source addresses enumerated from the host's interfaces, used
+    ///   for RFC 6724 destination address selection (see [`sort_by_rfc6724`]); `None` disables
+    ///   RFC 6724 ordering entirely, while `Some(&[])` enables it in its precedence-and-scope-only
+    ///   fallback mode
+    /// * `happy_eyeballs` - opt in to interleaving the combined `Ipv4AndIpv6` result by address
+    ///   family (see [`interleave_by_family`]) instead of returning it in whatever order the two
+    ///   queries happened to finish in. Only takes effect when `strategy` is
+    ///   [`LookupIpStrategy::Ipv4AndIpv6`], the only strategy that resolves both families
+    ///   concurrently; it's a no-op, not an error, to set this with any other strategy. Passing
+    ///   `false` leaves the combined result in the order the two queries finished in.
     pub fn lookup(
         names: Vec<Name>,
         strategy: LookupIpStrategy,
@@ -209,9 +549,13 @@ where
         options: DnsRequestOptions,
         hosts: Option<Arc<Hosts>>,
         finally_ip_addr: Option<RData>,
+        sortlist: Arc<[SortlistEntry]>,
+        rfc6724_sources: Option<Arc<[IpAddr]>>,
+        happy_eyeballs: bool,
     ) -> Self {
         let empty =
             ResolveError::from(ResolveErrorKind::Message("can not lookup IPs for no names"));
+        let interleave = happy_eyeballs && strategy == LookupIpStrategy::Ipv4AndIpv6;
         Self {
             names,
             strategy,
@@ -222,6 +566,9 @@ where
             options,
             hosts,
             finally_ip_addr,
+            sortlist,
+            interleave,
+            rfc6724_sources,
         }
     }
 }
@@ -291,8 +638,10 @@ where
     hosts_lookup(Query::query(name, RecordType::AAAA), client, options, hosts).await
 }
 
-// TODO: this really needs to have a stream interface
-/// queries only for A and AAAA in parallel
+/// queries only for A and AAAA in parallel, buffering both before returning
+///
+/// See [`lookup_ip_stream`] for a streaming variant that yields addresses from whichever family
+/// answers first instead of waiting on both.
 async fn ipv4_and_ipv6<C>(
     name: Name,
     client: CachingClient<C>,
@@ -344,6 +693,71 @@ where
     }
 }
 
+/// Resolves a single family for [`lookup_ip_stream`], turning the buffered [`hosts_lookup`] future
+/// into a stream of its individual addresses (or its error, as a single item) as soon as it
+/// completes.
+fn family_addr_stream<C>(
+    name: Name,
+    record_type: RecordType,
+    client: CachingClient<C>,
+    options: DnsRequestOptions,
+    hosts: Option<Arc<Hosts>>,
+) -> Pin<Box<dyn Stream<Item = Result<IpAddr, ResolveError>> + Send>>
+where
+    C: DnsHandle + 'static,
+{
+    hosts_lookup(Query::query(name, record_type), client, options, hosts)
+        .map(|result| match result {
+            Ok(lookup) => {
+                stream::iter(lookup.iter().filter_map(rdata_to_ip).collect::<Vec<_>>())
+                    .map(Ok)
+                    .boxed()
+            }
+            Err(e) => stream::once(future::ready(Err(e))).boxed(),
+        })
+        .flatten_stream()
+        .boxed()
+}
+
+/// Streaming counterpart to [`ipv4_and_ipv6`]: resolves A and AAAA concurrently, but yields
+/// addresses from whichever family answers first immediately, instead of buffering until both
+/// queries complete. This lets a Happy-Eyeballs-style connector start dialing the first-resolved
+/// family without waiting on the slower one.
+///
+/// If neither family yields any address, `finally_ip_addr` (when set) is yielded as a last resort,
+/// mirroring [`LookupIpFuture`]'s fallback behavior.
+pub fn lookup_ip_stream<C>(
+    name: Name,
+    client: CachingClient<C>,
+    options: DnsRequestOptions,
+    hosts: Option<Arc<Hosts>>,
+    finally_ip_addr: Option<RData>,
+) -> impl Stream<Item = Result<IpAddr, ResolveError>>
+where
+    C: DnsHandle + 'static,
+{
+    let v4 = family_addr_stream(name.clone(), RecordType::A, client.clone(), options, hosts.clone());
+    let v6 = family_addr_stream(name, RecordType::AAAA, client, options, hosts);
+
+    let saw_addr = Arc::new(AtomicBool::new(false));
+    let saw_addr_mark = saw_addr.clone();
+    let combined = stream::select(v4, v6).inspect(move |result| {
+        if result.is_ok() {
+            saw_addr_mark.store(true, Ordering::Relaxed);
+        }
+    });
+
+    let fallback = stream::once(async move {
+        match (saw_addr.load(Ordering::Relaxed), finally_ip_addr) {
+            (false, Some(ip_addr)) => rdata_to_ip(&ip_addr).map(Ok),
+            _ => None,
+        }
+    })
+    .filter_map(future::ready);
+
+    combined.chain(fallback)
+}
+
 /// queries only for AAAA and on no results queries for A
 async fn ipv6_then_ipv4<C>(
     name: Name,
@@ -434,6 +848,79 @@ where
     }
 }
 
+/// Sends the same query to every name server in `name_servers` concurrently and returns each
+/// server's own result, instead of `CachingClient`'s usual sequential/first-success behavior.
+///
+/// This is meant for diagnostics: comparing the individual answers can surface divergence between
+/// servers (e.g. split-horizon DNS or on-path injection) that a normal lookup, which only ever
+/// surfaces the first success, would hide. See [`consensus_addrs`] for a convenience that
+/// summarizes the set of addresses a majority of responding servers agree on.
+pub async fn multi_server_lookup<C>(
+    name: Name,
+    record_type: RecordType,
+    name_servers: Vec<(NameServerConfig, CachingClient<C>)>,
+    options: DnsRequestOptions,
+    hosts: Option<Arc<Hosts>>,
+) -> Vec<(NameServerConfig, Result<Lookup, ResolveError>)>
+where
+    C: DnsHandle + 'static,
+{
+    let query = Query::query(name, record_type);
+    let lookups = name_servers.into_iter().map(|(config, client)| {
+        let query = query.clone();
+        let hosts = hosts.clone();
+        async move {
+            let result = hosts_lookup(query, client, options, hosts).await;
+            (config, result)
+        }
+    });
+
+    future::join_all(lookups).await
+}
+
+/// Summarizes the results of a [`multi_server_lookup`] fan-out: the set of `IpAddr`s that a
+/// strict majority of the servers which answered successfully agree on, deduplicating repeated
+/// addresses within a single server's own answer so one chatty server can't outvote the others.
+///
+/// Returns `None` if no server answered successfully, or if no address reaches a majority (e.g.
+/// every responding server disagrees).
+pub fn consensus_addrs(
+    results: &[(NameServerConfig, Result<Lookup, ResolveError>)],
+) -> Option<Vec<IpAddr>> {
+    use std::collections::{HashMap, HashSet};
+
+    let mut counts: HashMap<IpAddr, usize> = HashMap::new();
+    let mut responders = 0usize;
+
+    for (_, result) in results {
+        let Ok(lookup) = result else {
+            continue;
+        };
+        responders += 1;
+        let addrs: HashSet<IpAddr> = lookup.iter().filter_map(rdata_to_ip).collect();
+        for addr in addrs {
+            *counts.entry(addr).or_insert(0) += 1;
+        }
+    }
+
+    if responders == 0 {
+        return None;
+    }
+
+    let mut majority: Vec<IpAddr> = counts
+        .into_iter()
+        .filter(|(_, count)| *count * 2 > responders)
+        .map(|(addr, _)| addr)
+        .collect();
+    majority.sort();
+
+    if majority.is_empty() {
+        None
+    } else {
+        Some(majority)
+    }
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
@@ -498,6 +985,16 @@ pub(crate) mod tests {
         Ok(DnsResponse::from_message(Message::new()).unwrap())
     }
 
+    fn v4_message_with(addr: Ipv4Addr) -> Result<DnsResponse, ProtoError> {
+        let mut message = Message::new();
+        message.add_query(Query::query(Name::root(), RecordType::A));
+        message.insert_answers(vec![Record::from_rdata(Name::root(), 86400, RData::A(addr.into()))]);
+
+        let resp = DnsResponse::from_message(message).unwrap();
+        assert!(resp.contains_answer());
+        Ok(resp)
+    }
+
     pub(crate) fn error() -> Result<DnsResponse, ProtoError> {
         Err(ProtoError::from("forced test failure"))
     }
@@ -724,4 +1221,335 @@ pub(crate) mod tests {
             vec![Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)]
         );
     }
+
+    #[test]
+    fn test_multi_server_lookup_returns_one_result_per_server() {
+        subscribe();
+
+        let honest = NameServerConfig::udp_and_tcp(IpAddr::from(Ipv4Addr::new(192, 0, 2, 1)));
+        let tampering = NameServerConfig::udp_and_tcp(IpAddr::from(Ipv4Addr::new(192, 0, 2, 2)));
+
+        let results = block_on(multi_server_lookup(
+            Name::root(),
+            RecordType::A,
+            vec![
+                (
+                    honest.clone(),
+                    CachingClient::new(0, mock(vec![v4_message_with(Ipv4Addr::new(10, 0, 0, 1))]), false),
+                ),
+                (
+                    tampering.clone(),
+                    CachingClient::new(0, mock(vec![v4_message_with(Ipv4Addr::new(10, 0, 0, 99))]), false),
+                ),
+            ],
+            DnsRequestOptions::default(),
+            None,
+        ));
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, honest);
+        assert_eq!(results[1].0, tampering);
+        assert!(results[0].1.is_ok());
+        assert!(results[1].1.is_ok());
+    }
+
+    #[test]
+    fn test_consensus_addrs_picks_majority_and_ignores_minority() {
+        let a = NameServerConfig::udp_and_tcp(IpAddr::from(Ipv4Addr::new(192, 0, 2, 1)));
+        let b = NameServerConfig::udp_and_tcp(IpAddr::from(Ipv4Addr::new(192, 0, 2, 2)));
+        let c = NameServerConfig::udp_and_tcp(IpAddr::from(Ipv4Addr::new(192, 0, 2, 3)));
+
+        let agreed = IpAddr::from(Ipv4Addr::new(10, 0, 0, 1));
+        let tampered = IpAddr::from(Ipv4Addr::new(10, 0, 0, 99));
+
+        let lookup_with = |addr: IpAddr| {
+            Lookup::new_with_max_ttl(
+                Query::query(Name::root(), RecordType::A),
+                Arc::from([Record::from_rdata(
+                    Name::root(),
+                    86400,
+                    match addr {
+                        IpAddr::V4(v4) => RData::A(v4.into()),
+                        IpAddr::V6(v6) => RData::AAAA(v6.into()),
+                    },
+                )]),
+            )
+        };
+
+        let results = vec![
+            (a, Ok(lookup_with(agreed))),
+            (b, Ok(lookup_with(agreed))),
+            (c, Ok(lookup_with(tampered))),
+        ];
+
+        assert_eq!(consensus_addrs(&results), Some(vec![agreed]));
+    }
+
+    #[test]
+    fn test_consensus_addrs_no_majority_is_none() {
+        let a = NameServerConfig::udp_and_tcp(IpAddr::from(Ipv4Addr::new(192, 0, 2, 1)));
+        let b = NameServerConfig::udp_and_tcp(IpAddr::from(Ipv4Addr::new(192, 0, 2, 2)));
+        let c = NameServerConfig::udp_and_tcp(IpAddr::from(Ipv4Addr::new(192, 0, 2, 3)));
+
+        // The two servers that answered disagree, so neither address is held by a strict
+        // majority of them; the failed server is not counted.
+        let results = vec![
+            (
+                c,
+                Ok(Lookup::new_with_max_ttl(
+                    Query::query(Name::root(), RecordType::A),
+                    Arc::from([Record::from_rdata(
+                        Name::root(),
+                        86400,
+                        RData::A(Ipv4Addr::new(10, 0, 0, 99).into()),
+                    )]),
+                )),
+            ),
+            (
+                a,
+                Ok(Lookup::new_with_max_ttl(
+                    Query::query(Name::root(), RecordType::A),
+                    Arc::from([Record::from_rdata(
+                        Name::root(),
+                        86400,
+                        RData::A(Ipv4Addr::new(10, 0, 0, 1).into()),
+                    )]),
+                )),
+            ),
+            (
+                b,
+                Err(ResolveError::from(ResolveErrorKind::Message(
+                    "forced test failure",
+                ))),
+            ),
+        ];
+
+        assert_eq!(consensus_addrs(&results), None);
+    }
+
+    #[test]
+    fn test_lookup_ip_stream_yields_addresses_from_both_families() {
+        subscribe();
+        let client = CachingClient::new(0, mock(vec![v6_message(), v4_message()]), false);
+
+        let mut addrs: Vec<IpAddr> = block_on(
+            lookup_ip_stream(
+                Name::root(),
+                client,
+                DnsRequestOptions::default(),
+                None,
+                None,
+            )
+            .filter_map(|result| future::ready(result.ok()))
+            .collect::<Vec<_>>(),
+        );
+        addrs.sort();
+
+        assert_eq!(
+            addrs,
+            vec![
+                IpAddr::from(Ipv4Addr::LOCALHOST),
+                IpAddr::from(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lookup_ip_stream_falls_back_when_both_families_empty() {
+        subscribe();
+        let client = CachingClient::new(0, mock(vec![empty(), empty()]), false);
+        let finally_ip_addr = RData::A(Ipv4Addr::new(127, 0, 0, 2).into());
+
+        let addrs: Vec<Result<IpAddr, ResolveError>> = block_on(
+            lookup_ip_stream(
+                Name::root(),
+                client,
+                DnsRequestOptions::default(),
+                None,
+                Some(finally_ip_addr),
+            )
+            .collect::<Vec<_>>(),
+        );
+
+        assert_eq!(addrs.len(), 1);
+        assert_eq!(
+            addrs[0].as_ref().unwrap(),
+            &IpAddr::from(Ipv4Addr::new(127, 0, 0, 2))
+        );
+    }
+
+    #[test]
+    fn test_lookup_ip_stream_no_fallback_once_an_address_was_seen() {
+        subscribe();
+        let client = CachingClient::new(0, mock(vec![empty(), v4_message()]), false);
+        let finally_ip_addr = RData::A(Ipv4Addr::new(127, 0, 0, 2).into());
+
+        let addrs: Vec<IpAddr> = block_on(
+            lookup_ip_stream(
+                Name::root(),
+                client,
+                DnsRequestOptions::default(),
+                None,
+                Some(finally_ip_addr),
+            )
+            .filter_map(|result| future::ready(result.ok()))
+            .collect::<Vec<_>>(),
+        );
+
+        assert_eq!(addrs, vec![IpAddr::from(Ipv4Addr::LOCALHOST)]);
+    }
+
+    #[test]
+    fn test_sort_by_sortlist_orders_by_first_matching_network() {
+        let addrs = vec![
+            IpAddr::from(Ipv4Addr::new(10, 0, 0, 5)),
+            IpAddr::from(Ipv4Addr::new(192, 168, 0, 7)),
+            IpAddr::from(Ipv4Addr::new(172, 16, 0, 1)),
+            IpAddr::from(Ipv4Addr::new(192, 168, 0, 1)),
+        ];
+
+        let sortlist = [
+            SortlistEntry::new(
+                IpAddr::from(Ipv4Addr::new(192, 168, 0, 0)),
+                IpAddr::from(Ipv4Addr::new(255, 255, 255, 0)),
+            ),
+            SortlistEntry::new(
+                IpAddr::from(Ipv4Addr::new(10, 0, 0, 0)),
+                IpAddr::from(Ipv4Addr::new(255, 0, 0, 0)),
+            ),
+        ];
+
+        // 192.168.0.0/24 addresses sort first (stable relative order preserved), then 10.0.0.0/8,
+        // then the unmatched 172.16.0.1 last.
+        assert_eq!(
+            sort_by_sortlist(addrs, &sortlist),
+            vec![
+                IpAddr::from(Ipv4Addr::new(192, 168, 0, 7)),
+                IpAddr::from(Ipv4Addr::new(192, 168, 0, 1)),
+                IpAddr::from(Ipv4Addr::new(10, 0, 0, 5)),
+                IpAddr::from(Ipv4Addr::new(172, 16, 0, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sort_by_sortlist_empty_is_noop() {
+        let addrs = vec![
+            IpAddr::from(Ipv4Addr::new(192, 168, 0, 7)),
+            IpAddr::from(Ipv4Addr::new(10, 0, 0, 5)),
+        ];
+
+        assert_eq!(sort_by_sortlist(addrs.clone(), &[]), addrs);
+    }
+
+    #[test]
+    fn test_interleave_by_family_alternates_starting_with_first_seen_family() {
+        let addrs = vec![
+            IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)),
+            IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 2)),
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)),
+        ];
+
+        assert_eq!(
+            interleave_by_family(addrs),
+            vec![
+                IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)),
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 2)),
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_interleave_by_family_appends_leftovers_from_longer_family() {
+        let addrs = vec![
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+            IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)),
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)),
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 3)),
+        ];
+
+        assert_eq!(
+            interleave_by_family(addrs),
+            vec![
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)),
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)),
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_interleave_by_family_single_family_is_noop() {
+        let addrs = vec![
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)),
+        ];
+
+        assert_eq!(interleave_by_family(addrs.clone()), addrs);
+    }
+
+    #[test]
+    fn test_sort_by_rfc6724_prefers_loopback_precedence() {
+        let addrs = vec![
+            IpAddr::V6(Ipv6Addr::new(0x2001, 0, 0, 0, 0, 0, 0, 1)),
+            IpAddr::V6(Ipv6Addr::LOCALHOST),
+        ];
+
+        assert_eq!(
+            sort_by_rfc6724(addrs, &[]),
+            vec![
+                IpAddr::V6(Ipv6Addr::LOCALHOST),
+                IpAddr::V6(Ipv6Addr::new(0x2001, 0, 0, 0, 0, 0, 0, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sort_by_rfc6724_prefers_ipv4_mapped_over_teredo() {
+        // IPv4-mapped (precedence 35) outranks 2001::/32 Teredo (precedence 5).
+        let v4 = IpAddr::from(Ipv4Addr::new(192, 0, 2, 1));
+        let addrs = vec![IpAddr::V6(Ipv6Addr::new(0x2001, 0, 0, 0, 0, 0, 0, 1)), v4];
+
+        assert_eq!(sort_by_rfc6724(addrs, &[])[0], v4);
+    }
+
+    #[test]
+    fn test_sort_by_rfc6724_prefers_destination_with_usable_source() {
+        let v4 = IpAddr::from(Ipv4Addr::new(192, 0, 2, 1));
+        let v6 = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+        let sources = [IpAddr::from(Ipv4Addr::new(192, 0, 2, 100))];
+
+        // Only the IPv4 destination has a same-family source address, so it sorts first even
+        // though its rule 6 precedence (35) is lower than the native IPv6 global precedence (40).
+        assert_eq!(sort_by_rfc6724(vec![v6, v4], &sources), vec![v4, v6]);
+    }
+
+    #[test]
+    fn test_sort_by_rfc6724_breaks_ties_by_longest_matching_prefix() {
+        let source = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x100));
+        let close = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x101));
+        let far = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0xffff, 0, 0, 0, 0, 1));
+
+        assert_eq!(
+            sort_by_rfc6724(vec![far, close], &[source]),
+            vec![close, far]
+        );
+    }
+
+    #[test]
+    fn test_sort_by_rfc6724_empty_sources_falls_back_to_precedence_and_scope() {
+        let global = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+        let link_local = IpAddr::V6(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1));
+
+        // With no source table, rule 8 falls back to comparing each destination's own scope:
+        // link-local (2) sorts before global (14).
+        assert_eq!(
+            sort_by_rfc6724(vec![global, link_local], &[]),
+            vec![link_local, global]
+        );
+    }
 }
@@ -0,0 +1,186 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! macOS resolver configuration discovery
+//!
+//! macOS ships a `/etc/resolv.conf` like other Unixes, but it is not the whole story: scoped
+//! resolvers for specific domains are configured as individual files under `/etc/resolver/*`
+//! (one per domain, in the same directive syntax as `resolv.conf`), and the authoritative live
+//! view is exposed through `scutil --dns` / the SystemConfiguration framework rather than a
+//! single file. We read the base file the same way other Unixes do, then shell out to
+//! `scutil --dns` and fold in any scoped resolver files we find, so lookups also pick up
+//! nameservers that only ever existed in the SystemConfiguration dynamic store and were never
+//! written to a file under `/etc`.
+
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+use std::process::Command;
+
+use crate::config::{NameServerConfig, ResolverConfig, ResolverOpts};
+use crate::proto::ProtoError;
+
+use super::unix::{self, UnixSystemConfSource};
+use super::SystemConfSource;
+
+const SCOPED_RESOLVER_DIR: &str = "/etc/resolver";
+
+/// Reads macOS's resolver configuration: the base `/etc/resolv.conf`, plus any additional
+/// nameservers reported by `scutil --dns` or configured via scoped resolvers under
+/// `/etc/resolver/*`.
+#[derive(Default)]
+pub(crate) struct MacosSystemConfSource {
+    unix: UnixSystemConfSource,
+}
+
+impl SystemConfSource for MacosSystemConfSource {
+    fn read_system_conf(&self) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+        let (mut config, options) = self.unix.read_system_conf()?;
+
+        for extra in extra_nameservers() {
+            config.add_name_server(extra);
+        }
+
+        Ok((config, options))
+    }
+
+    fn read_system_conf_with_fallback(
+        &self,
+        fallback: Vec<NameServerConfig>,
+    ) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+        let (mut config, options) = self.unix.read_system_conf_with_fallback(fallback)?;
+
+        for extra in extra_nameservers() {
+            config.add_name_server(extra);
+        }
+
+        Ok((config, options))
+    }
+}
+
+/// Nameservers macOS exposes beyond the base `/etc/resolv.conf`: `scutil --dns`'s live view,
+/// plus whatever's declared in scoped resolver files.
+fn extra_nameservers() -> Vec<NameServerConfig> {
+    let mut nameservers = scutil_dns_nameservers();
+    nameservers.extend(scoped_resolver_nameservers(SCOPED_RESOLVER_DIR));
+    nameservers
+}
+
+/// Runs `scutil --dns` and returns the nameservers it reports across every resolver entry.
+///
+/// Returns an empty list, rather than an error, if `scutil` isn't on `PATH`, exits non-zero, or
+/// prints something we don't understand: this is strictly additive to the file-based sources
+/// above, so a shell-out going wrong should degrade to "didn't find anything extra", not break
+/// resolution entirely.
+fn scutil_dns_nameservers() -> Vec<NameServerConfig> {
+    let Ok(output) = Command::new("scutil").arg("--dns").output() else {
+        return vec![];
+    };
+    if !output.status.success() {
+        return vec![];
+    }
+    let Ok(stdout) = String::from_utf8(output.stdout) else {
+        return vec![];
+    };
+
+    parse_scutil_dns(&stdout)
+}
+
+/// Parses the `nameserver[N] : <address>` lines out of `scutil --dns`'s resolver listing, e.g.:
+///
+/// ```text
+/// resolver #1
+///   nameserver[0] : 192.168.1.1
+///   nameserver[1] : 2001:db8::1
+/// ```
+///
+/// Lines that aren't a `nameserver[N]` entry, or whose address doesn't parse, are silently
+/// skipped, the same way a single bad option in `/etc/resolv.conf` no longer takes down the
+/// whole parse.
+fn parse_scutil_dns(output: &str) -> Vec<NameServerConfig> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let key = key.trim();
+            if !(key.starts_with("nameserver[") && key.ends_with(']')) {
+                return None;
+            }
+            value.trim().parse::<IpAddr>().ok()
+        })
+        .map(NameServerConfig::udp_and_tcp)
+        .collect()
+}
+
+/// Best-effort scan of `/etc/resolver/*`, returning the nameservers declared in each scoped
+/// resolver file. Unreadable files and files with no usable directives are silently skipped, the
+/// same way a single bad option in `/etc/resolv.conf` no longer takes down the whole parse.
+fn scoped_resolver_nameservers(
+    dir: impl AsRef<Path>,
+) -> Vec<crate::config::NameServerConfig> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .filter_map(|data| {
+            unix::parse_resolv_conf(&data)
+                .or_else(|_| unix::parse_resolv_conf_lenient(&data))
+                .ok()
+        })
+        .flat_map(|(config, _)| config.name_servers().to_vec())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    use super::parse_scutil_dns;
+
+    #[test]
+    fn test_parse_scutil_dns() {
+        let output = "DNS configuration\n\n\
+resolver #1\n\
+  search domain[0] : example.com\n\
+  nameserver[0] : 192.168.1.1\n\
+  nameserver[1] : 2001:db8::1\n\
+  if_index : 5 (en0)\n\
+  flags    : Request A records, Request AAAA records\n\
+  reach    : 0x00020002 (Reachable,Directly Reachable Address)\n\n\
+resolver #2\n\
+  domain   : local\n\
+  options  : mdns\n\
+  timeout  : 5\n";
+
+        let nameservers = parse_scutil_dns(output);
+        assert_eq!(
+            nameservers.iter().map(|ns| ns.ip).collect::<Vec<_>>(),
+            vec![
+                IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)),
+                IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_scutil_dns_ignores_garbage() {
+        let output = "DNS configuration\n\n\
+resolver #1\n\
+  nameserver[0] : not-an-ip\n\
+  search domain[0] : example.com\n";
+
+        assert!(parse_scutil_dns(output).is_empty());
+    }
+
+    #[test]
+    fn test_parse_scutil_dns_empty() {
+        assert!(parse_scutil_dns("").is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Platform-specific system configuration loading
+//!
+//! `read_system_conf()` discovers the nameservers, search domains, and options that the host
+//! operating system would use by default. Where that configuration lives differs wildly by
+//! platform, so platform-specific discovery is modeled as pluggable [`SystemConfSource`]
+//! implementations, and `read_system_conf()` simply dispatches to the right one at compile time.
+
+mod unix;
+pub use unix::{parse_resolv_conf, parse_resolv_conf_lenient, parse_resolv_conf_with_fallback};
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(windows)]
+mod windows;
+
+use crate::config::{NameServerConfig, ResolverConfig, ResolverOpts};
+use crate::proto::ProtoError;
+
+/// A source of system-provided resolver configuration.
+///
+/// Each platform exposes its default resolver configuration differently: most Unixes through
+/// `/etc/resolv.conf`, Windows through the IP Helper API/registry, and macOS through a
+/// combination of `/etc/resolv.conf`, scoped resolvers under `/etc/resolver/*`, and `scutil
+/// --dns`. A `SystemConfSource` hides that difference behind a single method so
+/// `read_system_conf()` can return the same `(ResolverConfig, ResolverOpts)` tuple everywhere.
+pub trait SystemConfSource {
+    /// Reads this source's view of the system resolver configuration.
+    fn read_system_conf(&self) -> Result<(ResolverConfig, ResolverOpts), ProtoError>;
+
+    /// Like [`read_system_conf`](Self::read_system_conf), except that `fallback` is substituted
+    /// for the nameserver list instead of returning an error when the source has none. Sources
+    /// that have no notion of "no nameservers found" (i.e. can only fail for other reasons) can
+    /// rely on the default implementation, which just ignores `fallback`.
+    fn read_system_conf_with_fallback(
+        &self,
+        fallback: Vec<NameServerConfig>,
+    ) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+        let _ = fallback;
+        self.read_system_conf()
+    }
+}
+
+/// Reads the resolver configuration from the host operating system, using whichever
+/// [`SystemConfSource`] is appropriate for the current platform.
+#[cfg(windows)]
+pub fn read_system_conf() -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+    windows::WindowsSystemConfSource.read_system_conf()
+}
+
+/// Reads the resolver configuration from the host operating system, using whichever
+/// [`SystemConfSource`] is appropriate for the current platform.
+#[cfg(target_os = "macos")]
+pub fn read_system_conf() -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+    macos::MacosSystemConfSource::default().read_system_conf()
+}
+
+/// Reads the resolver configuration from the host operating system, using whichever
+/// [`SystemConfSource`] is appropriate for the current platform.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn read_system_conf() -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+    unix::UnixSystemConfSource::default().read_system_conf()
+}
+
+/// Like [`read_system_conf`], except that `fallback` is substituted for the nameserver list
+/// instead of returning an error when the host has none configured.
+#[cfg(windows)]
+pub fn read_system_conf_with_fallback(
+    fallback: Vec<NameServerConfig>,
+) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+    windows::WindowsSystemConfSource.read_system_conf_with_fallback(fallback)
+}
+
+/// Like [`read_system_conf`], except that `fallback` is substituted for the nameserver list
+/// instead of returning an error when the host has none configured.
+#[cfg(target_os = "macos")]
+pub fn read_system_conf_with_fallback(
+    fallback: Vec<NameServerConfig>,
+) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+    macos::MacosSystemConfSource::default().read_system_conf_with_fallback(fallback)
+}
+
+/// Like [`read_system_conf`], except that `fallback` is substituted for the nameserver list
+/// instead of returning an error when the host has none configured.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn read_system_conf_with_fallback(
+    fallback: Vec<NameServerConfig>,
+) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+    unix::UnixSystemConfSource::default().read_system_conf_with_fallback(fallback)
+}
@@ -5,32 +5,78 @@
 // https://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-//! System configuration loading
-//!
-//! This module is responsible for parsing and returning the configuration from
-//!  the host system. It will read from the default location on each operating
-//!  system, e.g. most Unixes have this written to `/etc/resolv.conf`
+//! `resolv.conf(5)` parsing, shared by all Unix-like [`super::SystemConfSource`] implementations.
 
+use std::env;
 use std::fs::File;
 use std::io;
 use std::io::Read;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::path::Path;
 use std::str::FromStr;
 use std::time::Duration;
 
 use crate::config::{NameServerConfig, ResolverConfig, ResolverOpts};
+use crate::lookup_ip::SortlistEntry;
 use crate::proto::ProtoError;
 use crate::proto::rr::Name;
 
-pub fn read_system_conf() -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
-    read_resolv_conf("/etc/resolv.conf")
+/// Converts a `resolv_conf` sortlist network into our own `SortlistEntry`, defaulting to a
+/// host-only netmask (all ones) when the file didn't specify one.
+fn into_sortlist_entry(network: &resolv_conf::Network) -> SortlistEntry {
+    let netmask = network.netmask.unwrap_or(match network.addr {
+        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::new(255, 255, 255, 255)),
+        IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from([0xff; 16])),
+    });
+    SortlistEntry::new(network.addr, netmask)
+}
+
+/// The default [`super::SystemConfSource`] for Unix-like systems: reads `/etc/resolv.conf` and
+/// falls back to lenient parsing if the strict parse rejects the file.
+pub(crate) struct UnixSystemConfSource {
+    path: &'static str,
+}
+
+impl Default for UnixSystemConfSource {
+    fn default() -> Self {
+        Self {
+            path: "/etc/resolv.conf",
+        }
+    }
+}
+
+impl super::SystemConfSource for UnixSystemConfSource {
+    fn read_system_conf(&self) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+        read_resolv_conf(self.path)
+    }
+
+    fn read_system_conf_with_fallback(
+        &self,
+        fallback: Vec<NameServerConfig>,
+    ) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+        read_resolv_conf_with_fallback(self.path, fallback)
+    }
 }
 
 fn read_resolv_conf<P: AsRef<Path>>(path: P) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
     let mut data = String::new();
     let mut file = File::open(path)?;
     file.read_to_string(&mut data)?;
-    parse_resolv_conf(&data)
+    parse_resolv_conf(&data).or_else(|_| parse_resolv_conf_lenient(&data))
+}
+
+/// Like [`read_system_conf`](super::read_system_conf), except that `fallback` is substituted for
+/// the nameserver list whenever the file has none, instead of returning an error. This lets
+/// downstream users mirror the common "fall back to a public resolver" behavior while leaving the
+/// strict default (`read_system_conf`) intact for callers that would rather fail loudly.
+pub(crate) fn read_resolv_conf_with_fallback<P: AsRef<Path>>(
+    path: P,
+    fallback: Vec<NameServerConfig>,
+) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+    let mut data = String::new();
+    let mut file = File::open(path)?;
+    file.read_to_string(&mut data)?;
+    parse_resolv_conf_with_fallback(&data, fallback)
 }
 
 pub fn parse_resolv_conf<T: AsRef<[u8]>>(
@@ -38,12 +84,195 @@ pub fn parse_resolv_conf<T: AsRef<[u8]>>(
 ) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
     let parsed_conf = resolv_conf::Config::parse(&data)
         .map_err(|e| io::Error::other(format!("Error parsing resolv.conf: {e}")))?;
-    into_resolver_config(parsed_conf)
+    let (config, options) = into_resolver_config(parsed_conf, None)?;
+    Ok(apply_env_overrides(config, options))
+}
+
+/// Like [`parse_resolv_conf`], except that `fallback` is substituted for the nameserver list
+/// whenever the parsed file (strict or lenient) has none, instead of returning an error.
+pub fn parse_resolv_conf_with_fallback<T: AsRef<[u8]>>(
+    data: T,
+    fallback: Vec<NameServerConfig>,
+) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+    let result = match resolv_conf::Config::parse(&data) {
+        Ok(parsed_conf) => into_resolver_config(parsed_conf, Some(fallback)),
+        Err(_) => parse_resolv_conf_lenient_impl(&data, Some(fallback)),
+    }?;
+    Ok(apply_env_overrides(result.0, result.1))
+}
+
+/// A forgiving line-based fallback for files that `resolv_conf::Config::parse` rejects outright.
+///
+/// `resolv_conf` is strict: a single vendor-specific or newer directive/option anywhere in the
+/// file causes the whole parse to fail, which would otherwise leave the resolver with zero
+/// nameservers on an otherwise-usable file. This scans line by line and only extracts the small
+/// set of directives we actually consume (`nameserver`, `search`/`domain`, and the `options` we
+/// understand), silently ignoring anything else.
+pub fn parse_resolv_conf_lenient<T: AsRef<[u8]>>(
+    data: T,
+) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+    let (config, options) = parse_resolv_conf_lenient_impl(data, None)?;
+    Ok(apply_env_overrides(config, options))
+}
+
+fn parse_resolv_conf_lenient_impl<T: AsRef<[u8]>>(
+    data: T,
+    fallback: Option<Vec<NameServerConfig>>,
+) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+    let data = String::from_utf8_lossy(data.as_ref());
+
+    let mut nameservers = vec![];
+    let mut search = vec![];
+    let mut domain = None;
+    let mut options = ResolverOpts::default();
+
+    for line in data.lines() {
+        let mut tokens = line.split_whitespace();
+        let Some(directive) = tokens.next() else {
+            continue;
+        };
+
+        match directive {
+            "nameserver" => {
+                if let Some(ip) = tokens.next().and_then(|ip| ip.parse().ok()) {
+                    nameservers.push(NameServerConfig::udp_and_tcp(ip));
+                }
+            }
+            "search" => {
+                search = tokens
+                    .filter(|domain| *domain != "--")
+                    .filter_map(|domain| Name::from_str_relaxed(domain).ok())
+                    .collect();
+            }
+            "domain" => {
+                domain = tokens.next().and_then(|domain| Name::from_str(domain).ok());
+            }
+            "options" => {
+                for option in tokens {
+                    match option.split_once(':') {
+                        Some(("ndots", value)) => {
+                            if let Ok(ndots) = value.parse() {
+                                options.ndots = ndots;
+                            }
+                        }
+                        Some(("timeout", value)) => {
+                            if let Ok(secs) = value.parse() {
+                                options.timeout = Duration::from_secs(secs);
+                            }
+                        }
+                        Some(("attempts", value)) => {
+                            if let Ok(attempts) = value.parse() {
+                                options.attempts = attempts;
+                            }
+                        }
+                        Some(_) => {}
+                        None => match option {
+                            "edns0" => options.edns0 = true,
+                            "rotate" => options.rotate = true,
+                            "use-vc" => options.use_vc = true,
+                            "single-request" => options.single_request = true,
+                            "single-request-reopen" => options.single_request_reopen = true,
+                            "no-check-names" => options.no_check_names = true,
+                            "trust-ad" => options.trust_ad = true,
+                            _ => {}
+                        },
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let nameservers = match (nameservers.is_empty(), fallback) {
+        (true, Some(fallback)) => fallback,
+        (true, None) => Err(io::Error::other("no nameservers found in config"))?,
+        (false, _) => nameservers,
+    };
+
+    let config = ResolverConfig::from_parts(domain, search, nameservers);
+    Ok((config, options))
+}
+
+/// Applies glibc's `LOCALDOMAIN`/`RES_OPTIONS` environment overrides on top of a parsed config.
+///
+/// glibc's `res_init` layers these on top of whatever `/etc/resolv.conf` specifies: `LOCALDOMAIN`
+/// fully replaces the search list, and `RES_OPTIONS` overrides individual option fields. We apply
+/// the same behavior here so containerized deployments can tune the resolver without rewriting
+/// the file.
+fn apply_env_overrides(
+    config: ResolverConfig,
+    options: ResolverOpts,
+) -> (ResolverConfig, ResolverOpts) {
+    apply_resolv_conf_overrides(
+        config,
+        options,
+        env::var("LOCALDOMAIN").ok().as_deref(),
+        env::var("RES_OPTIONS").ok().as_deref(),
+    )
+}
+
+/// The pure core of [`apply_env_overrides`], taking `LOCALDOMAIN`/`RES_OPTIONS` as plain
+/// arguments instead of reading them from the process environment.
+///
+/// Split out so tests can exercise the override parsing directly, without mutating real
+/// process-global environment variables (which races with every other test in the same process).
+fn apply_resolv_conf_overrides(
+    mut config: ResolverConfig,
+    mut options: ResolverOpts,
+    local_domain: Option<&str>,
+    res_options: Option<&str>,
+) -> (ResolverConfig, ResolverOpts) {
+    if let Some(local_domain) = local_domain {
+        let search = local_domain
+            .split_whitespace()
+            .filter_map(|domain| Name::from_str_relaxed(domain).ok())
+            .collect::<Vec<_>>();
+
+        if !search.is_empty() {
+            config = ResolverConfig::from_parts(config.domain().cloned(), search, config.name_servers().to_vec());
+        }
+    }
+
+    if let Some(res_options) = res_options {
+        for token in res_options.split_whitespace() {
+            match token.split_once(':') {
+                Some(("ndots", value)) => {
+                    if let Ok(ndots) = value.parse() {
+                        options.ndots = ndots;
+                    }
+                }
+                Some(("timeout", value)) => {
+                    if let Ok(secs) = value.parse() {
+                        options.timeout = Duration::from_secs(secs);
+                    }
+                }
+                Some(("attempts", value)) => {
+                    if let Ok(attempts) = value.parse() {
+                        options.attempts = attempts;
+                    }
+                }
+                Some(_) => {}
+                None => match token {
+                    "edns0" => options.edns0 = true,
+                    "rotate" => options.rotate = true,
+                    "use-vc" => options.use_vc = true,
+                    "single-request" => options.single_request = true,
+                    "single-request-reopen" => options.single_request_reopen = true,
+                    "no-check-names" => options.no_check_names = true,
+                    "trust-ad" => options.trust_ad = true,
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    (config, options)
 }
 
 // TODO: use a custom parsing error type maybe?
 fn into_resolver_config(
     parsed_config: resolv_conf::Config,
+    fallback: Option<Vec<NameServerConfig>>,
 ) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
     let domain = if let Some(domain) = parsed_config.get_system_domain() {
         // The system domain name maybe appear to be valid to the resolv_conf
@@ -61,9 +290,11 @@ fn into_resolver_config(
         .iter()
         .map(|ip| NameServerConfig::udp_and_tcp(ip.into()))
         .collect::<Vec<_>>();
-    if nameservers.is_empty() {
-        Err(io::Error::other("no nameservers found in config"))?;
-    }
+    let nameservers = match (nameservers.is_empty(), fallback) {
+        (true, Some(fallback)) => fallback,
+        (true, None) => Err(io::Error::other("no nameservers found in config"))?,
+        (false, _) => nameservers,
+    };
 
     // search
     let mut search = vec![];
@@ -81,11 +312,24 @@ fn into_resolver_config(
 
     let config = ResolverConfig::from_parts(domain, search, nameservers);
 
+    let sortlist = parsed_config
+        .sortlist
+        .iter()
+        .map(into_sortlist_entry)
+        .collect::<Vec<_>>();
+
     let options = ResolverOpts {
         ndots: parsed_config.ndots as usize,
         timeout: Duration::from_secs(u64::from(parsed_config.timeout)),
         attempts: parsed_config.attempts as usize,
         edns0: parsed_config.edns0,
+        rotate: parsed_config.rotate,
+        use_vc: parsed_config.use_vc,
+        single_request: parsed_config.single_request,
+        single_request_reopen: parsed_config.single_request_reopen,
+        no_check_names: parsed_config.no_check_names,
+        trust_ad: parsed_config.trust_ad,
+        sortlist,
         ..ResolverOpts::default()
     };
 
@@ -171,6 +415,125 @@ mod tests {
         is_default_opts(parsed.1);
     }
 
+    #[test]
+    fn test_localdomain_env_override() {
+        // Exercises `apply_resolv_conf_overrides` directly with an explicit override value,
+        // rather than `parse_resolv_conf` with a real `LOCALDOMAIN` set via `env::set_var`: the
+        // process environment is global, so mutating it here would race with every other test in
+        // this process that touches `LOCALDOMAIN`.
+        let (config, options) =
+            parse_resolv_conf_lenient_impl("search localnet.\nnameserver 127.0.0.1", None)
+                .expect("failed");
+        let (config, _options) = apply_resolv_conf_overrides(
+            config,
+            options,
+            Some("example.com other.example."),
+            None,
+        );
+
+        assert_eq!(
+            config.search(),
+            &[
+                Name::from_str_relaxed("example.com").unwrap(),
+                Name::from_str_relaxed("other.example.").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_res_options_env_override() {
+        // See `test_localdomain_env_override` above for why this exercises the override parsing
+        // directly instead of going through a real `RES_OPTIONS` env var.
+        let (config, options) =
+            parse_resolv_conf_lenient_impl("nameserver 127.0.0.1", None).expect("failed");
+        let (_config, options) = apply_resolv_conf_overrides(
+            config,
+            options,
+            None,
+            Some("ndots:3 timeout:2 attempts:4 edns0 rotate trust-ad"),
+        );
+
+        assert_eq!(options.ndots, 3);
+        assert_eq!(options.timeout, Duration::from_secs(2));
+        assert_eq!(options.attempts, 4);
+        assert!(options.edns0);
+        assert!(options.rotate);
+        assert!(options.trust_ad);
+    }
+
+    #[test]
+    fn test_remaining_options_mapped() {
+        let parsed = parse_resolv_conf(
+            "nameserver 127.0.0.1\noptions rotate use-vc single-request single-request-reopen no-check-names trust-ad\n",
+        )
+        .expect("failed");
+
+        assert!(parsed.1.rotate);
+        assert!(parsed.1.use_vc);
+        assert!(parsed.1.single_request);
+        assert!(parsed.1.single_request_reopen);
+        assert!(parsed.1.no_check_names);
+        assert!(parsed.1.trust_ad);
+    }
+
+    #[test]
+    fn test_sortlist_parsed() {
+        let parsed = parse_resolv_conf(
+            "nameserver 127.0.0.1\nsortlist 130.155.160.0/255.255.240.0 130.155.0.0\n",
+        )
+        .expect("failed");
+
+        assert_eq!(parsed.1.sortlist.len(), 2);
+    }
+
+    #[test]
+    fn test_lenient_fallback_skips_unknown_options() {
+        // `options fake-vendor-option` is not recognized by `resolv_conf`, so the strict parse
+        // fails; the lenient fallback should still recover the nameserver.
+        let data = "nameserver 127.0.0.1\noptions fake-vendor-option edns0\n";
+        assert!(parse_resolv_conf(data).is_err());
+
+        let parsed = parse_resolv_conf_lenient(data).expect("lenient parse failed");
+        assert_eq!(
+            parsed.0.name_servers()[0].ip,
+            IpAddr::from_str("127.0.0.1").unwrap()
+        );
+        assert!(parsed.1.edns0);
+    }
+
+    #[test]
+    fn test_parse_resolv_conf_with_fallback_used_when_empty() {
+        let fallback = vec![NameServerConfig::udp_and_tcp(
+            IpAddr::from_str("9.9.9.9").unwrap(),
+        )];
+
+        // No `nameserver` lines at all, so both the strict and lenient parsers would otherwise
+        // error out; the fallback should be substituted instead.
+        let parsed =
+            parse_resolv_conf_with_fallback("search example.com\n", fallback.clone()).unwrap();
+        assert_eq!(parsed.0.name_servers(), fallback.as_slice());
+
+        // Also exercise the lenient fallback path, where the strict parse fails outright.
+        let data = "options fake-vendor-option\n";
+        assert!(parse_resolv_conf(data).is_err());
+        let parsed = parse_resolv_conf_with_fallback(data, fallback.clone()).unwrap();
+        assert_eq!(parsed.0.name_servers(), fallback.as_slice());
+    }
+
+    #[test]
+    fn test_parse_resolv_conf_with_fallback_ignored_when_present() {
+        let fallback = vec![NameServerConfig::udp_and_tcp(
+            IpAddr::from_str("9.9.9.9").unwrap(),
+        )];
+
+        let parsed =
+            parse_resolv_conf_with_fallback("nameserver 127.0.0.1\n", fallback).unwrap();
+        assert_eq!(
+            parsed.0.name_servers()[0].ip,
+            IpAddr::from_str("127.0.0.1").unwrap()
+        );
+    }
+
     #[test]
     fn test_read_resolv_conf() {
         read_resolv_conf(format!("{}/resolv.conf-simple", tests_dir())).expect("simple failed");
@@ -0,0 +1,82 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Windows resolver configuration discovery
+//!
+//! Windows has no `/etc/resolv.conf`; the nameservers a process should use live in the IP Helper
+//! API (surfaced to us via the `ipconfig` crate, which wraps `GetAdaptersAddresses`) and are
+//! ultimately backed by the registry. We walk the network adapters that are up and not loopback,
+//! and collect the DNS servers each one reports.
+
+use std::io;
+use std::net::IpAddr;
+
+use crate::config::{NameServerConfig, ResolverConfig, ResolverOpts};
+use crate::proto::ProtoError;
+
+use super::SystemConfSource;
+
+/// Reads resolver configuration from the Windows IP Helper API via the `ipconfig` crate, since
+/// there is no `resolv.conf`-style file to parse on this platform.
+pub(crate) struct WindowsSystemConfSource;
+
+impl SystemConfSource for WindowsSystemConfSource {
+    fn read_system_conf(&self) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+        self.read_system_conf_with_fallback_impl(None)
+    }
+
+    fn read_system_conf_with_fallback(
+        &self,
+        fallback: Vec<NameServerConfig>,
+    ) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+        self.read_system_conf_with_fallback_impl(Some(fallback))
+    }
+}
+
+impl WindowsSystemConfSource {
+    fn read_system_conf_with_fallback_impl(
+        &self,
+        fallback: Option<Vec<NameServerConfig>>,
+    ) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+        let adapters = ipconfig::get_adapters().map_err(io::Error::from)?;
+
+        let mut name_servers = Vec::new();
+        for adapter in adapters {
+            // Only adapters that are actually in use should contribute nameservers.
+            if adapter.oper_status() != ipconfig::OperStatus::IfOperStatusUp
+                || adapter.if_type() == ipconfig::IfType::SoftwareLoopback
+            {
+                continue;
+            }
+
+            for address in adapter.dns_servers() {
+                let address: IpAddr = *address;
+                // link-local IPv6 DNS servers are not usable without a scope id, which
+                // `ipconfig` does not currently expose; skip them rather than emit an
+                // address we cannot actually reach.
+                if let IpAddr::V6(v6) = address {
+                    if v6.segments()[0] & 0xffc0 == 0xfe80 {
+                        continue;
+                    }
+                }
+
+                name_servers.push(NameServerConfig::udp_and_tcp(address));
+            }
+        }
+
+        let name_servers = match (name_servers.is_empty(), fallback) {
+            (true, Some(fallback)) => fallback,
+            (true, None) => {
+                return Err(io::Error::other("no nameservers found in system preferences").into());
+            }
+            (false, _) => name_servers,
+        };
+
+        let config = ResolverConfig::from_parts(None, vec![], name_servers);
+        Ok((config, ResolverOpts::default()))
+    }
+}